@@ -0,0 +1,186 @@
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Turns a local path into a `file://` URL; URLs are passed through.
+fn to_url(path_or_url: &str) -> Result<String, String> {
+    if let Ok(url) = reqwest::Url::parse(path_or_url) {
+        // Single-letter "schemes" are Windows drive letters, not URLs
+        if url.scheme().len() > 1 {
+            return Ok(url.to_string());
+        }
+    }
+
+    let path = std::fs::canonicalize(path_or_url)
+        .map_err(|e| format!("Cannot open '{}': {}", path_or_url, e))?;
+    reqwest::Url::from_file_path(&path)
+        .map(|url| url.to_string())
+        .map_err(|_| format!("Cannot convert {:?} to a file URL", path))
+}
+
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Splits a command line on whitespace while honouring double quotes, which
+/// is all registry and `.desktop` Exec lines need.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Replaces URL placeholders (`%1`, `%u`, `%U`, ...) in a launcher command,
+/// appending the URL if the command has none, and drops other field codes.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn substitute_url(parts: Vec<String>, url: &str) -> Vec<String> {
+    let mut substituted = false;
+    let mut result: Vec<String> = parts
+        .into_iter()
+        .filter_map(|part| match part.as_str() {
+            "%1" | "%u" | "%U" | "%f" | "%F" | "%l" | "%L" => {
+                substituted = true;
+                Some(url.to_string())
+            }
+            p if p.len() == 2 && p.starts_with('%') => None,
+            _ => Some(part.replace("%1", url)),
+        })
+        .collect();
+
+    if !substituted {
+        result.push(url.to_string());
+    }
+    result
+}
+
+#[cfg(target_os = "macos")]
+async fn browser_command(url: &str) -> Result<Vec<String>, String> {
+    let handlers = command_output(
+        "defaults",
+        &["read", "com.apple.LaunchServices/com.apple.launchservices.secure", "LSHandlers"],
+    )
+    .await
+    .unwrap_or_default();
+
+    // Each handler is a plist dict; find the one for the https/http scheme
+    let bundle_id = handlers.split('}').find_map(|entry| {
+        let is_web = entry.contains("LSHandlerURLScheme = https;") || entry.contains("LSHandlerURLScheme = http;");
+        if !is_web {
+            return None;
+        }
+        entry.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("LSHandlerRoleAll = ")
+                .map(|id| id.trim_end_matches(';').trim_matches('"').to_string())
+        })
+    });
+
+    // With no explicit choice recorded, Safari is the system default browser
+    let bundle_id = bundle_id.unwrap_or_else(|| "com.apple.safari".to_string());
+    Ok(vec!["open".into(), "-b".into(), bundle_id, url.to_string()])
+}
+
+#[cfg(target_os = "windows")]
+async fn browser_command(url: &str) -> Result<Vec<String>, String> {
+    let user_choice = command_output(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice",
+            "/v",
+            "ProgId",
+        ],
+    )
+    .await
+    .ok_or_else(|| "No default browser is configured".to_string())?;
+
+    let prog_id = user_choice
+        .lines()
+        .find_map(|line| line.split("REG_SZ").nth(1))
+        .map(|id| id.trim().to_string())
+        .ok_or_else(|| "No default browser is configured".to_string())?;
+
+    let key = format!(r"HKCR\{}\shell\open\command", prog_id);
+    let command = command_output("reg", &["query", &key, "/ve"])
+        .await
+        .and_then(|out| out.lines().find_map(|l| l.split("REG_SZ").nth(1)).map(|c| c.trim().to_string()))
+        .ok_or_else(|| format!("Default browser '{}' has no launch command", prog_id))?;
+
+    Ok(substitute_url(split_command_line(&command), url))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn browser_command(url: &str) -> Result<Vec<String>, String> {
+    if let Ok(browser) = std::env::var("BROWSER") {
+        if !browser.trim().is_empty() {
+            return Ok(substitute_url(split_command_line(&browser), url));
+        }
+    }
+
+    let desktop_file = command_output("xdg-settings", &["get", "default-web-browser"])
+        .await
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| "No default browser is configured (xdg-settings returned nothing)".to_string())?;
+
+    let mut search_dirs = Vec::new();
+    if let Some(data) = dirs::data_dir() {
+        search_dirs.push(data.join("applications"));
+    }
+    search_dirs.push("/usr/local/share/applications".into());
+    search_dirs.push("/usr/share/applications".into());
+    search_dirs.push("/var/lib/flatpak/exports/share/applications".into());
+
+    for dir in search_dirs {
+        let Ok(content) = tokio::fs::read_to_string(dir.join(&desktop_file)).await else {
+            continue;
+        };
+        if let Some(exec) = content.lines().find_map(|l| l.strip_prefix("Exec=")) {
+            return Ok(substitute_url(split_command_line(exec), url));
+        }
+    }
+
+    Err(format!("Default browser '{}' could not be found", desktop_file))
+}
+
+/// Opens a URL or local file (e.g. an exported HTML preview) in the system's
+/// default web browser, rather than whatever app is associated with the file
+/// type.
+#[tauri::command]
+pub async fn open_in_browser(path_or_url: String) -> Result<(), String> {
+    let url = to_url(&path_or_url)?;
+    let command = browser_command(&url).await?;
+
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "Default browser has an empty launch command".to_string())?;
+
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to launch browser '{}': {}", program, e))?;
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use crate::fs_utils;
+
+/// How long a tool gets to print its version or help text.
+const HELP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Help text keyed by tool name and the version it reported, so an upgraded
+/// tool is asked again.
+pub type HelpCache = Arc<Mutex<HashMap<(String, String), String>>>;
+
+pub fn create_help_cache() -> HelpCache {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Runs `tool` with a single argument and returns its exit status along with
+/// stdout and stderr combined, or `None` if it couldn't start or timed out.
+async fn run_with_timeout(tool: &str, arg: &str) -> Option<(bool, String)> {
+    let child = Command::new(tool)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+
+    let output = tokio::time::timeout(HELP_TIMEOUT, child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(stderr.trim());
+    }
+
+    Some((output.status.success(), text))
+}
+
+/// Returns a CLI tool's usage text from `--help`, falling back to `-h`.
+/// Results are cached per tool and version.
+#[tauri::command]
+pub async fn get_tool_help(
+    name: String,
+    cache: tauri::State<'_, HelpCache>,
+) -> Result<String, String> {
+    let version = run_with_timeout(&name, "--version")
+        .await
+        .filter(|(success, _)| *success)
+        .map(|(_, text)| text)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let key = (name.clone(), version);
+    if let Some(help) = cache.lock().await.get(&key) {
+        return Ok(help.clone());
+    }
+
+    let mut help = None;
+    for flag in ["--help", "-h"] {
+        // Many tools print usage to stderr or exit non-zero for `-h`, so any
+        // output counts
+        if let Some((_, text)) = run_with_timeout(&name, flag).await {
+            if !text.is_empty() {
+                help = Some(text);
+                break;
+            }
+        }
+    }
+
+    let help = help.ok_or_else(|| format!("'{}' did not print any help text", name))?;
+    cache.lock().await.insert(key, help.clone());
+
+    Ok(help)
+}
+
+/// A CLI tool the user added to detect alongside the built-in ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomCliTool {
+    /// Shown in the tool list and used for its id (`cli-{name}`).
+    pub name: String,
+    /// Program name looked up on `PATH`, or an absolute path.
+    pub command: String,
+}
+
+fn custom_tools_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::settings::settings_dir()?.join("cli_tools.json"))
+}
+
+/// Loads the custom tool list; a missing file means none were added.
+pub async fn load_custom_tools() -> Result<Vec<CustomCliTool>, String> {
+    let path = custom_tools_path()?;
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid custom tool list at {:?}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read custom tool list at {:?}: {}", path, e)),
+    }
+}
+
+pub fn validate_custom_tools(tools: &[CustomCliTool]) -> Result<(), String> {
+    let mut names = std::collections::HashSet::new();
+    for tool in tools {
+        if tool.name.trim().is_empty() || tool.command.trim().is_empty() {
+            return Err("Custom tools need a name and a command".to_string());
+        }
+        if !names.insert(tool.name.as_str()) {
+            return Err(format!("Duplicate custom tool '{}'", tool.name));
+        }
+    }
+    Ok(())
+}
+
+pub async fn save_custom_tools(tools: &[CustomCliTool]) -> Result<(), String> {
+    validate_custom_tools(tools)?;
+
+    let path = custom_tools_path()?;
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(tools).map_err(|e| e.to_string())?;
+    fs_utils::write_atomic(&path, json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write custom tool list at {:?}: {}", path, e))
+}
+
+/// Returns the CLI tools added on top of the built-in ones.
+#[tauri::command]
+pub async fn get_custom_cli_tools() -> Result<Vec<CustomCliTool>, String> {
+    load_custom_tools().await
+}
+
+/// Replaces the list of CLI tools `detect_cli_tools` looks for in addition
+/// to the built-in ones.
+#[tauri::command]
+pub async fn set_custom_cli_tools(tools: Vec<CustomCliTool>) -> Result<(), String> {
+    save_custom_tools(&tools).await
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSnapshot {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionChange {
+    pub id: String,
+    pub name: String,
+    pub previous_version: String,
+    pub version: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CliToolsDiff {
+    pub added: Vec<ToolSnapshot>,
+    pub removed: Vec<ToolSnapshot>,
+    pub version_changed: Vec<VersionChange>,
+}
+
+fn snapshot_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::settings::cache_dir()?.join("cli_tools_snapshot.json"))
+}
+
+fn to_snapshot(tool: &serde_json::Value) -> Option<ToolSnapshot> {
+    Some(ToolSnapshot {
+        id: tool.get("id")?.as_str()?.to_string(),
+        name: tool.get("name")?.as_str()?.to_string(),
+        version: tool
+            .pointer("/metadata/version")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+    })
+}
+
+fn diff_snapshots(previous: &[ToolSnapshot], current: &[ToolSnapshot]) -> CliToolsDiff {
+    let mut diff = CliToolsDiff::default();
+
+    for tool in current {
+        match previous.iter().find(|p| p.id == tool.id) {
+            None => diff.added.push(tool.clone()),
+            Some(old) if old.version != tool.version => diff.version_changed.push(VersionChange {
+                id: tool.id.clone(),
+                name: tool.name.clone(),
+                previous_version: old.version.clone(),
+                version: tool.version.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    diff.removed = previous
+        .iter()
+        .filter(|p| !current.iter().any(|tool| tool.id == p.id))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+/// Compares detected CLI tools against the snapshot saved by the previous
+/// call, then replaces the snapshot. On the first call every tool is `added`.
+#[tauri::command]
+pub async fn detect_cli_tools_diff() -> Result<CliToolsDiff, String> {
+    let current: Vec<ToolSnapshot> = crate::detect_cli_tools()
+        .await?
+        .iter()
+        .filter_map(to_snapshot)
+        .collect();
+
+    let path = snapshot_path()?;
+    // A missing or unreadable snapshot just means there's nothing to compare to
+    let previous: Vec<ToolSnapshot> = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+
+    let diff = diff_snapshots(&previous, &current);
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&current).map_err(|e| e.to_string())?;
+    fs_utils::write_atomic(&path, json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write tool snapshot at {:?}: {}", path, e))?;
+
+    Ok(diff)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchPathEntry {
+    pub path: String,
+    pub exists: bool,
+}
+
+/// Returns the PATH directories tool detection searches, in order, with
+/// duplicates removed and whether each directory exists.
+#[tauri::command]
+pub async fn get_search_path() -> Result<Vec<SearchPathEntry>, String> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut seen = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+
+    for dir in std::env::split_paths(&path) {
+        if dir.as_os_str().is_empty() || !seen.insert(dir.clone()) {
+            continue;
+        }
+        entries.push(SearchPathEntry {
+            exists: tokio::fs::metadata(&dir).await.map(|m| m.is_dir()).unwrap_or(false),
+            path: dir.to_string_lossy().to_string(),
+        });
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::cli_tools::{self, CustomCliTool};
+use crate::fs_utils;
+use crate::mcp_config;
+use crate::settings::{self, Settings};
+
+const BUNDLE_VERSION: u32 = 1;
+
+/// Portable snapshot of the app's configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub settings: Settings,
+    /// User-wide MCP server definitions keyed by server id.
+    #[serde(default)]
+    pub mcp_servers: Map<String, Value>,
+    /// CLI tools added on top of the built-in ones.
+    #[serde(default)]
+    pub cli_tools: Vec<CustomCliTool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigConflict {
+    /// `settings`, `mcp_server` or `cli_tool`.
+    pub kind: String,
+    /// Server id for `mcp_server` conflicts, tool name for `cli_tool` ones.
+    pub id: Option<String>,
+    pub existing: Value,
+    pub incoming: Value,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub settings_applied: bool,
+    pub servers_applied: Vec<String>,
+    pub cli_tools_applied: Vec<String>,
+    /// Entries that differ from what's already configured and were left
+    /// untouched. Import again with `overwrite` to apply them.
+    pub conflicts: Vec<ConfigConflict>,
+}
+
+/// The MCP config file imports write to: the user-wide one with the lowest
+/// precedence, so project-local configs keep overriding it.
+fn user_config_path() -> Result<String, String> {
+    mcp_config::config_paths()
+        .into_iter()
+        .next()
+        .filter(|path| path.is_absolute())
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| "Could not find home directory".to_string())
+}
+
+/// Collects server definitions from the user-wide MCP configs, later files
+/// overriding earlier ones. The project-local config isn't portable.
+async fn user_mcp_servers() -> Result<Map<String, Value>, String> {
+    let mut servers = Map::new();
+
+    for path in mcp_config::config_paths().into_iter().filter(|p| p.is_absolute()) {
+        let (mut config, original) = mcp_config::read_config_for_edit(&path.to_string_lossy()).await?;
+        if original.is_none() {
+            continue;
+        }
+        for (id, definition) in mcp_config::servers_map(&mut config).iter() {
+            servers.insert(id.clone(), definition.clone());
+        }
+    }
+
+    Ok(servers)
+}
+
+/// Writes settings, user-wide MCP server definitions and custom CLI tools to
+/// a single JSON file.
+#[tauri::command]
+pub async fn export_config(path: String) -> Result<(), String> {
+    let bundle = ConfigBundle {
+        version: BUNDLE_VERSION,
+        settings: settings::load_settings().await?,
+        mcp_servers: user_mcp_servers().await?,
+        cli_tools: cli_tools::load_custom_tools().await?,
+    };
+
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs_utils::write_atomic(Path::new(&path), json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Validates a bundle written by `export_config` and applies it. Settings,
+/// servers and CLI tools that differ from the current configuration are
+/// reported as conflicts and skipped unless `overwrite` is set.
+#[tauri::command]
+pub async fn import_config(path: String, overwrite: Option<bool>) -> Result<ImportReport, String> {
+    let overwrite = overwrite.unwrap_or(false);
+
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let bundle: ConfigBundle = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid config bundle {}: {}", path, e))?;
+
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Config bundle version {} is newer than this app supports ({})",
+            bundle.version, BUNDLE_VERSION
+        ));
+    }
+
+    // Validate the whole bundle before touching any config
+    bundle.settings.validate()?;
+    for (id, definition) in &bundle.mcp_servers {
+        mcp_config::validate_server_definition(definition)
+            .map_err(|e| format!("Invalid definition for MCP server '{}': {}", id, e))?;
+    }
+    cli_tools::validate_custom_tools(&bundle.cli_tools)?;
+
+    // Read and merge every target first, so a config that can't be read or
+    // parsed fails the import before anything is written
+    let mut report = ImportReport::default();
+
+    let settings_exist = tokio::fs::try_exists(settings::settings_path()?)
+        .await
+        .unwrap_or(false);
+    let current_settings = settings::load_settings().await?;
+    if current_settings != bundle.settings {
+        if settings_exist && !overwrite {
+            report.conflicts.push(ConfigConflict {
+                kind: "settings".to_string(),
+                id: None,
+                existing: serde_json::to_value(&current_settings).map_err(|e| e.to_string())?,
+                incoming: serde_json::to_value(&bundle.settings).map_err(|e| e.to_string())?,
+            });
+        } else {
+            report.settings_applied = true;
+        }
+    }
+
+    let config_path = user_config_path()?;
+    let (mut config, original) = mcp_config::read_config_for_edit(&config_path).await?;
+    let servers = mcp_config::servers_map(&mut config);
+
+    for (id, incoming) in bundle.mcp_servers {
+        match servers.get(&id).cloned() {
+            Some(existing) if existing == incoming => {}
+            Some(existing) if !overwrite => report.conflicts.push(ConfigConflict {
+                kind: "mcp_server".to_string(),
+                id: Some(id),
+                existing,
+                incoming,
+            }),
+            _ => {
+                servers.insert(id.clone(), incoming);
+                report.servers_applied.push(id);
+            }
+        }
+    }
+
+    let mut tools = cli_tools::load_custom_tools().await?;
+    for incoming in bundle.cli_tools {
+        match tools.iter().position(|tool| tool.name == incoming.name) {
+            Some(i) if tools[i] == incoming => {}
+            Some(i) if !overwrite => report.conflicts.push(ConfigConflict {
+                kind: "cli_tool".to_string(),
+                id: Some(incoming.name.clone()),
+                existing: serde_json::to_value(&tools[i]).map_err(|e| e.to_string())?,
+                incoming: serde_json::to_value(&incoming).map_err(|e| e.to_string())?,
+            }),
+            Some(i) => {
+                report.cli_tools_applied.push(incoming.name.clone());
+                tools[i] = incoming;
+            }
+            None => {
+                report.cli_tools_applied.push(incoming.name.clone());
+                tools.push(incoming);
+            }
+        }
+    }
+
+    if report.settings_applied {
+        settings::save_settings(&bundle.settings).await?;
+    }
+    if !report.servers_applied.is_empty() {
+        mcp_config::write_config(&config_path, &config, original.as_deref()).await?;
+    }
+    if !report.cli_tools_applied.is_empty() {
+        cli_tools::save_custom_tools(&tools).await?;
+    }
+
+    Ok(report)
+}
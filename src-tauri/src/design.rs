@@ -0,0 +1,1066 @@
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::fs_utils;
+
+/// Merges `new` onto the shape of `existing`: keys keep their existing order,
+/// keys that were removed are dropped, and new keys are appended in the order
+/// they appear in `new`. This keeps diffs of saved designs small.
+pub fn merge_preserving_order(existing: &Value, new: Value) -> Value {
+    match (existing, new) {
+        (Value::Object(old_map), Value::Object(mut new_map)) => {
+            let mut merged = serde_json::Map::new();
+
+            for (key, old_value) in old_map {
+                // shift_remove keeps the remaining new keys in their order
+                if let Some(new_value) = new_map.shift_remove(key) {
+                    merged.insert(key.clone(), merge_preserving_order(old_value, new_value));
+                }
+            }
+            for (key, new_value) in new_map {
+                merged.insert(key, new_value);
+            }
+
+            Value::Object(merged)
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => Value::Array(
+            new_items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| match old_items.get(i) {
+                    Some(old_item) => merge_preserving_order(old_item, item),
+                    None => item,
+                })
+                .collect(),
+        ),
+        (_, new) => new,
+    }
+}
+
+/// Detects the indentation unit of a pretty-printed JSON document, falling
+/// back to two spaces.
+pub fn detect_indent(text: &str) -> String {
+    text.lines()
+        .skip(1)
+        .map(|line| {
+            line.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .find(|indent| !indent.is_empty())
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+pub fn to_pretty_json(value: &Value, indent: &str) -> Result<String, String> {
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer).map_err(|e| e.to_string())?;
+    String::from_utf8(out).map_err(|e| e.to_string())
+}
+
+/// Saves a design while keeping the existing file's key order, indentation
+/// and trailing newline, then writes atomically. Returns `false` when the
+/// content on disk was already identical and nothing was written.
+#[tauri::command]
+pub async fn save_design_minimal(path: String, new_json: String) -> Result<bool, String> {
+    let new_value: Value =
+        serde_json::from_str(&new_json).map_err(|e| format!("Invalid design JSON: {}", e))?;
+
+    let existing_text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // An unparseable existing file has no structure worth preserving
+    let existing = existing_text
+        .as_deref()
+        .and_then(|text| serde_json::from_str::<Value>(text).ok().map(|v| (text, v)));
+
+    let output = match existing {
+        Some((text, existing_value)) => {
+            let merged = merge_preserving_order(&existing_value, new_value);
+            let mut output = to_pretty_json(&merged, &detect_indent(text))?;
+            if text.ends_with('\n') {
+                output.push('\n');
+            }
+            output
+        }
+        None => to_pretty_json(&new_value, "  ")?,
+    };
+
+    if existing_text.as_deref() == Some(output.as_str()) {
+        return Ok(false);
+    }
+
+    fs_utils::write_atomic(Path::new(&path), output.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+/// Copy of `value` with every object's keys sorted, recursively. Array order
+/// is meaningful (layer stacking) and kept.
+pub fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(entries.into_iter().map(|(k, v)| (k.clone(), sort_keys(v))).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sort_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Rewrites a design in canonical form: sorted keys, two-space indentation
+/// and a trailing newline, so version-control diffs only show real changes.
+/// The previous file is kept as `<path>.bak`. Returns `false` when the file
+/// was already canonical and nothing was written.
+#[tauri::command]
+pub async fn format_design(path: String) -> Result<bool, String> {
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let value: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid design JSON: {}", e))?;
+
+    let mut output = to_pretty_json(&sort_keys(&value), "  ")?;
+    output.push('\n');
+    if output == text {
+        return Ok(false);
+    }
+
+    let backup_path = format!("{}.bak", path);
+    tokio::fs::copy(&path, &backup_path)
+        .await
+        .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+    fs_utils::write_atomic(Path::new(&path), output.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonPointerError {
+    /// The pointer is well-formed but nothing exists at it.
+    NotFound { pointer: String },
+    /// Not an RFC 6901 pointer (must be empty or start with `/`).
+    InvalidPointer { pointer: String },
+    InvalidJson { message: String },
+    Io { message: String },
+}
+
+fn read_json_file(path: &str) -> Result<Value, JsonPointerError> {
+    let file = std::fs::File::open(path).map_err(|e| JsonPointerError::Io {
+        message: e.to_string(),
+    })?;
+    serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| JsonPointerError::InvalidJson {
+        message: e.to_string(),
+    })
+}
+
+/// Returns the value at an RFC 6901 pointer (e.g. `/document/canvas/width`)
+/// in a JSON file, so the frontend doesn't have to load the whole document.
+#[tauri::command]
+pub async fn read_json_pointer(path: String, pointer: String) -> Result<Value, JsonPointerError> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(JsonPointerError::InvalidPointer { pointer });
+    }
+
+    let document = tokio::task::spawn_blocking(move || read_json_file(&path))
+        .await
+        .map_err(|e| JsonPointerError::Io {
+            message: e.to_string(),
+        })??;
+
+    document
+        .pointer(&pointer)
+        .cloned()
+        .ok_or(JsonPointerError::NotFound { pointer })
+}
+
+fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Sets `new_value` at an RFC 6901 pointer. The last token may name a new
+/// object key or `-`/the length of an array to append. Missing intermediate
+/// keys become empty objects when `create_missing` is set.
+pub fn set_pointer(
+    document: &mut Value,
+    pointer: &str,
+    new_value: Value,
+    create_missing: bool,
+) -> Result<(), JsonPointerError> {
+    if pointer.is_empty() {
+        *document = new_value;
+        return Ok(());
+    }
+    if !pointer.starts_with('/') {
+        return Err(JsonPointerError::InvalidPointer {
+            pointer: pointer.to_string(),
+        });
+    }
+
+    let not_found = || JsonPointerError::NotFound {
+        pointer: pointer.to_string(),
+    };
+    let tokens: Vec<String> = pointer[1..].split('/').map(unescape_token).collect();
+    let (last, parents) = tokens.split_last().expect("split always yields a token");
+
+    let mut target = document;
+    for token in parents {
+        target = match target {
+            Value::Object(map) => {
+                if !map.contains_key(token) && !create_missing {
+                    return Err(not_found());
+                }
+                map.entry(token.clone())
+                    .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            }
+            Value::Array(items) => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(not_found)?,
+            _ => return Err(not_found()),
+        };
+    }
+
+    match target {
+        Value::Object(map) => {
+            map.insert(last.clone(), new_value);
+        }
+        Value::Array(items) if last == "-" => items.push(new_value),
+        Value::Array(items) => match last.parse::<usize>() {
+            Ok(index) if index < items.len() => items[index] = new_value,
+            Ok(index) if index == items.len() => items.push(new_value),
+            _ => return Err(not_found()),
+        },
+        _ => return Err(not_found()),
+    }
+
+    Ok(())
+}
+
+/// Sets one value in a JSON file by pointer and writes it back atomically,
+/// keeping the file's indentation and trailing newline. Returns the updated
+/// document.
+#[tauri::command]
+pub async fn write_json_pointer(
+    path: String,
+    pointer: String,
+    value: Value,
+    create_missing: Option<bool>,
+) -> Result<Value, JsonPointerError> {
+    let io_error = |e: std::io::Error| JsonPointerError::Io {
+        message: e.to_string(),
+    };
+
+    let text = tokio::fs::read_to_string(&path).await.map_err(io_error)?;
+    let mut document: Value =
+        serde_json::from_str(&text).map_err(|e| JsonPointerError::InvalidJson {
+            message: e.to_string(),
+        })?;
+
+    set_pointer(&mut document, &pointer, value, create_missing.unwrap_or(false))?;
+
+    let mut output = to_pretty_json(&document, &detect_indent(&text))
+        .map_err(|message| JsonPointerError::InvalidJson { message })?;
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+    // Never replace a good file with something that won't load again
+    serde_json::from_str::<Value>(&output).map_err(|e| JsonPointerError::InvalidJson {
+        message: e.to_string(),
+    })?;
+
+    fs_utils::write_atomic(Path::new(&path), output.as_bytes())
+        .await
+        .map_err(io_error)?;
+
+    Ok(document)
+}
+
+/// Checks a parsed `.beaki` file the way the app does when opening it: a
+/// `beaki-v1` project wrapping a document with the required fields.
+pub fn validate_beaki(project: &Value) -> Result<(), String> {
+    if project.get("_format").and_then(|f| f.as_str()) != Some("beaki-v1") {
+        return Err("Invalid file format. Expected beaki-v1 format.".to_string());
+    }
+
+    let document = project
+        .get("document")
+        .filter(|d| d.is_object())
+        .ok_or_else(|| "Invalid file format. Missing document data.".to_string())?;
+
+    let missing: Vec<&str> = ["id", "schemaVersion", "pages", "frames", "layers"]
+        .into_iter()
+        .filter(|field| document.get(field).map(|v| v.is_null()).unwrap_or(true))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Invalid document structure. Missing required fields: {}",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Renames a file, first checking that a file renamed to `.beaki` is a
+/// design the app can open, so a rename can't produce an unopenable design.
+/// Other renames go through unchecked. An existing `new_path` is only
+/// replaced when `overwrite` is set.
+#[tauri::command]
+pub async fn rename_with_validation(old_path: String, new_path: String, overwrite: Option<bool>) -> Result<(), String> {
+    // A case-only rename on a case-insensitive disk finds the file itself
+    let taken = match tokio::fs::symlink_metadata(&new_path).await {
+        Ok(_) => match (tokio::fs::canonicalize(&old_path).await, tokio::fs::canonicalize(&new_path).await) {
+            (Ok(old), Ok(new)) => old != new,
+            _ => true,
+        },
+        Err(_) => false,
+    };
+    if taken && !overwrite.unwrap_or(false) {
+        return Err(format!("{} already exists; pass overwrite to replace it", new_path));
+    }
+
+    let to_beaki = Path::new(&new_path)
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("beaki"))
+        .unwrap_or(false);
+
+    if to_beaki {
+        let text = tokio::fs::read_to_string(&old_path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", old_path, e))?;
+        let project = serde_json::from_str::<Value>(&text)
+            .map_err(|e| format!("Cannot rename to .beaki: {} is not valid JSON ({})", old_path, e))?;
+        validate_beaki(&project).map_err(|e| format!("Cannot rename to .beaki: {}", e))?;
+    }
+
+    tokio::fs::rename(&old_path, &new_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Document fields left out of the content id: timestamps rewritten on every
+/// save, the open page (UI state) and the document's own id, so a copy saved
+/// under a new id still dedupes with the original.
+const CONTENT_ID_EXCLUDED_FIELDS: &[&str] = &["id", "createdAt", "updatedAt", "activePageId"];
+
+/// Removes `provenance` objects at any depth; they record who touched an
+/// element and when, not what it is.
+fn strip_provenance(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.shift_remove("provenance");
+            map.values_mut().for_each(strip_provenance);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_provenance),
+        _ => {}
+    }
+}
+
+/// blake3 hex of a design's normalized content; see `design_content_id`.
+pub fn content_id(design: &Value) -> String {
+    // The project wrapper (`viewport`, `selection`, format version) is view
+    // state; only the document counts. A bare document is used as-is.
+    let mut document = design.get("document").unwrap_or(design).clone();
+    if let Some(map) = document.as_object_mut() {
+        for field in CONTENT_ID_EXCLUDED_FIELDS {
+            map.shift_remove(*field);
+        }
+    }
+    strip_provenance(&mut document);
+
+    let canonical = serde_json::to_vec(&sort_keys(&document)).unwrap_or_default();
+    blake3::hash(&canonical).to_hex().to_string()
+}
+
+/// Returns a content-addressed id for a design that survives reformatting
+/// and re-saving. It is the blake3 hash (lowercase hex) of the document with
+/// keys sorted, compactly serialized, after dropping:
+/// - the project wrapper: `_format`, `version`, `viewport`, `selection`
+/// - document `id`, `createdAt`, `updatedAt` and `activePageId`
+/// - every `provenance` object, at any depth
+///
+/// Everything else counts, including `schemaVersion`, `name` and `meta`.
+#[tauri::command]
+pub async fn design_content_id(path: String) -> Result<String, String> {
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let design: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid design JSON: {}", e))?;
+
+    tokio::task::spawn_blocking(move || content_id(&design))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Counts of the main collections in a `.beaki` design.
+#[derive(Debug, Default, Serialize)]
+pub struct DesignSummary {
+    pub name: Option<String>,
+    pub schema_version: Option<String>,
+    pub page_count: usize,
+    pub frame_count: usize,
+    pub layer_count: usize,
+    pub component_count: usize,
+    pub token_count: usize,
+    pub asset_count: usize,
+    /// Set when the file is malformed; the counts then cover only what was
+    /// scanned before the error.
+    pub error: Option<String>,
+}
+
+/// Counts the entries of a JSON object (or array) without keeping them.
+struct CountSeed<'a>(&'a mut usize);
+
+impl<'de> DeserializeSeed<'de> for CountSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CountSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an object or array")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while map.next_key::<IgnoredAny>()?.is_some() {
+            *self.0 += 1;
+            map.next_value::<IgnoredAny>()?;
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            *self.0 += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Fills a `DesignSummary` while scanning, so a parse error still leaves
+/// the counts gathered so far. Handles both the project file (with a
+/// `document` key) and a bare document.
+struct SummarySeed<'a>(&'a mut DesignSummary);
+
+impl<'de> DeserializeSeed<'de> for SummarySeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SummarySeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a design object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let summary = self.0;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "document" => map.next_value_seed(SummarySeed(&mut *summary))?,
+                "name" => summary.name = map.next_value::<Value>()?.as_str().map(String::from),
+                "schemaVersion" => {
+                    summary.schema_version = map.next_value::<Value>()?.as_str().map(String::from)
+                }
+                "pages" => map.next_value_seed(CountSeed(&mut summary.page_count))?,
+                "frames" => map.next_value_seed(CountSeed(&mut summary.frame_count))?,
+                "layers" => map.next_value_seed(CountSeed(&mut summary.layer_count))?,
+                "components" => map.next_value_seed(CountSeed(&mut summary.component_count))?,
+                "tokens" => map.next_value_seed(CountSeed(&mut summary.token_count))?,
+                "assets" => map.next_value_seed(CountSeed(&mut summary.asset_count))?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn summarize_reader<R: std::io::Read>(reader: R) -> DesignSummary {
+    let mut summary = DesignSummary::default();
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+
+    let result = SummarySeed(&mut summary)
+        .deserialize(&mut deserializer)
+        .and_then(|()| deserializer.end());
+    if let Err(e) = result {
+        summary.error = Some(e.to_string());
+    }
+
+    summary
+}
+
+/// Counts pages, frames, layers, components, tokens and assets of a design
+/// in one streaming pass, without loading the document into memory, for the
+/// project browser.
+#[tauri::command]
+pub async fn summarize_design(path: String) -> Result<DesignSummary, String> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path).map_err(|e| e.to_string())?;
+        Ok(summarize_reader(std::io::BufReader::new(file)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryStrategy {
+    /// The file parsed as-is.
+    Intact,
+    /// The file was cut short; everything up to the last complete value was
+    /// kept and the open objects/arrays were closed.
+    TruncatedRepair,
+    /// The file couldn't be salvaged; a backup or leftover save temp file
+    /// next to it was used instead.
+    Backup,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryResult {
+    pub document: Value,
+    pub strategy: RecoveryStrategy,
+    /// File the document was recovered from.
+    pub source: String,
+    /// Bytes cut off the end by `truncated_repair`.
+    pub bytes_dropped: Option<usize>,
+}
+
+/// How many cut points near the end of a damaged file are tried.
+const MAX_REPAIR_ATTEMPTS: usize = 512;
+
+/// Repairs JSON that was cut off mid-write: finds the last points where a
+/// value was complete (after a closing bracket, or before a comma), closes
+/// the containers still open there, and returns the first candidate that
+/// parses along with how many bytes were dropped.
+pub fn repair_truncated_json(text: &str) -> Option<(Value, usize)> {
+    let mut stack: Vec<u8> = Vec::new();
+    let mut cut_points: std::collections::VecDeque<(usize, Vec<u8>)> = Default::default();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, byte) in text.bytes().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        let cut = match byte {
+            b'"' => {
+                in_string = true;
+                None
+            }
+            b'{' | b'[' => {
+                stack.push(byte);
+                None
+            }
+            b'}' | b']' => {
+                stack.pop();
+                Some(i + 1)
+            }
+            b',' => Some(i),
+            _ => None,
+        };
+
+        if let Some(position) = cut {
+            if cut_points.len() == MAX_REPAIR_ATTEMPTS {
+                cut_points.pop_front();
+            }
+            cut_points.push_back((position, stack.clone()));
+        }
+    }
+
+    cut_points.into_iter().rev().find_map(|(position, open)| {
+        let mut candidate = text[..position].trim_end().to_string();
+        candidate.extend(open.iter().rev().map(|b| if *b == b'{' { '}' } else { ']' }));
+        serde_json::from_str(&candidate)
+            .ok()
+            .map(|value| (value, text.len() - position))
+    })
+}
+
+/// Files next to a design that may hold an intact copy, newest first:
+/// `.bak`/`~` backups and temp files left by interrupted atomic saves.
+async fn backup_candidates(path: &Path) -> Vec<std::path::PathBuf> {
+    let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    let mut candidates = vec![
+        path.with_file_name(format!("{}.bak", file_name)),
+        path.with_file_name(format!("{}~", file_name)),
+    ];
+
+    let temp_prefix = format!(".{}.tmp-", file_name);
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with(&temp_prefix) {
+                candidates.push(entry.path());
+            }
+        }
+    }
+
+    let mut dated = Vec::new();
+    for candidate in candidates {
+        if let Ok(modified) = tokio::fs::metadata(&candidate).await.and_then(|m| m.modified()) {
+            dated.push((modified, candidate));
+        }
+    }
+    dated.sort_by(|a, b| b.0.cmp(&a.0));
+    dated.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Opens a damaged `.beaki` file: parses it as-is if possible, else repairs
+/// a truncated write, else falls back to the newest backup that parses. The
+/// recovered document is returned, not written; the caller decides whether
+/// to save it.
+#[tauri::command]
+pub async fn recover_design(path: String) -> Result<RecoveryResult, String> {
+    let bytes = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    if let Ok(document) = serde_json::from_str::<Value>(&text) {
+        return Ok(RecoveryResult {
+            document,
+            strategy: RecoveryStrategy::Intact,
+            source: path,
+            bytes_dropped: None,
+        });
+    }
+
+    let repaired = {
+        let text = text.into_owned();
+        tokio::task::spawn_blocking(move || repair_truncated_json(&text))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    // A bare `{}` salvaged from the first brace isn't worth returning
+    if let Some((document, bytes_dropped)) = repaired.filter(|(v, _)| !is_empty_container(v)) {
+        return Ok(RecoveryResult {
+            document,
+            strategy: RecoveryStrategy::TruncatedRepair,
+            source: path,
+            bytes_dropped: Some(bytes_dropped),
+        });
+    }
+
+    for candidate in backup_candidates(Path::new(&path)).await {
+        let Ok(text) = tokio::fs::read_to_string(&candidate).await else {
+            continue;
+        };
+        if let Ok(document) = serde_json::from_str::<Value>(&text) {
+            return Ok(RecoveryResult {
+                document,
+                strategy: RecoveryStrategy::Backup,
+                source: candidate.to_string_lossy().to_string(),
+                bytes_dropped: None,
+            });
+        }
+    }
+
+    Err(format!("Could not recover {}: no repair or backup produced a valid document", path))
+}
+
+fn is_empty_container(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonChange {
+    pub kind: ChangeKind,
+    /// RFC 6901 pointer, into `after` for added and changed values and into
+    /// `before` for removed ones.
+    pub pointer: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn element_id(value: &Value) -> Option<&str> {
+    value.get("id").and_then(Value::as_str)
+}
+
+fn change(kind: ChangeKind, pointer: String, before: Option<&Value>, after: Option<&Value>) -> JsonChange {
+    JsonChange {
+        kind,
+        pointer,
+        before: before.cloned(),
+        after: after.cloned(),
+    }
+}
+
+fn diff_into(before: &Value, after: &Value, pointer: &str, changes: &mut Vec<JsonChange>) {
+    match (before, after) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let child = format!("{}/{}", pointer, escape_token(key));
+                match new_map.get(key) {
+                    Some(new_value) => diff_into(old_value, new_value, &child, changes),
+                    None => changes.push(change(ChangeKind::Removed, child, Some(old_value), None)),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    let child = format!("{}/{}", pointer, escape_token(key));
+                    changes.push(change(ChangeKind::Added, child, None, Some(new_value)));
+                }
+            }
+        }
+        // Design elements carry ids, so a reorder or an insertion in the
+        // middle doesn't show up as every later element changing
+        (Value::Array(old_items), Value::Array(new_items))
+            if old_items.iter().chain(new_items).all(|item| element_id(item).is_some()) =>
+        {
+            for (i, old_item) in old_items.iter().enumerate() {
+                if !new_items.iter().any(|item| element_id(item) == element_id(old_item)) {
+                    changes.push(change(ChangeKind::Removed, format!("{}/{}", pointer, i), Some(old_item), None));
+                }
+            }
+            for (i, new_item) in new_items.iter().enumerate() {
+                let child = format!("{}/{}", pointer, i);
+                match old_items.iter().find(|item| element_id(item) == element_id(new_item)) {
+                    Some(old_item) => diff_into(old_item, new_item, &child, changes),
+                    None => changes.push(change(ChangeKind::Added, child, None, Some(new_item))),
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (i, old_item) in old_items.iter().enumerate() {
+                let child = format!("{}/{}", pointer, i);
+                match new_items.get(i) {
+                    Some(new_item) => diff_into(old_item, new_item, &child, changes),
+                    None => changes.push(change(ChangeKind::Removed, child, Some(old_item), None)),
+                }
+            }
+            for (i, new_item) in new_items.iter().enumerate().skip(old_items.len()) {
+                changes.push(change(ChangeKind::Added, format!("{}/{}", pointer, i), None, Some(new_item)));
+            }
+        }
+        (old, new) if old != new => {
+            changes.push(change(ChangeKind::Changed, pointer.to_string(), Some(old), Some(new)));
+        }
+        _ => {}
+    }
+}
+
+/// Structural diff of two JSON documents. Objects are compared key by key;
+/// arrays whose items all have an `id` are matched by id, other arrays by
+/// index.
+pub fn diff_json(before: &Value, after: &Value) -> Vec<JsonChange> {
+    let mut changes = Vec::new();
+    diff_into(before, after, "", &mut changes);
+    changes
+}
+
+/// Loads one side of a diff, salvaging what it can from a damaged file.
+/// Returns the document (if any) and a warning when it isn't the whole file.
+async fn load_for_diff(path: &str) -> (Option<Value>, Option<String>) {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(e) => return (None, Some(format!("Could not read {}: {}", path, e))),
+    };
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+
+    if let Ok(document) = serde_json::from_str::<Value>(&text) {
+        return (Some(document), None);
+    }
+
+    let repaired = tokio::task::spawn_blocking(move || repair_truncated_json(&text))
+        .await
+        .ok()
+        .flatten()
+        .filter(|(v, _)| !is_empty_container(v));
+    match repaired {
+        Some((document, bytes_dropped)) => (
+            Some(document),
+            Some(format!(
+                "{} is damaged; compared what could be recovered ({} bytes at the end were dropped)",
+                path, bytes_dropped
+            )),
+        ),
+        None => (None, Some(format!("{} is not valid JSON and could not be repaired", path))),
+    }
+}
+
+/// Compares a design with an autosave snapshot so the user can see what a
+/// restore would change. A damaged side is repaired where possible (or
+/// treated as empty) and the result is marked `partial` with a warning.
+#[tauri::command]
+pub async fn diff_autosave(doc_path: String, snapshot_path: String) -> Result<Value, String> {
+    let ((document, doc_warning), (snapshot, snapshot_warning)) =
+        futures_util::join!(load_for_diff(&doc_path), load_for_diff(&snapshot_path));
+
+    if document.is_none() && snapshot.is_none() {
+        let warnings: Vec<String> = doc_warning.into_iter().chain(snapshot_warning).collect();
+        return Err(warnings.join("; "));
+    }
+
+    let empty = Value::Object(serde_json::Map::new());
+    let changes = diff_json(document.as_ref().unwrap_or(&empty), snapshot.as_ref().unwrap_or(&empty));
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    let summary = serde_json::json!({
+        "added": count(ChangeKind::Added),
+        "removed": count(ChangeKind::Removed),
+        "changed": count(ChangeKind::Changed),
+    });
+    let warnings: Vec<String> = doc_warning.into_iter().chain(snapshot_warning).collect();
+
+    Ok(serde_json::json!({
+        "changes": changes,
+        "summary": summary,
+        "partial": !warnings.is_empty(),
+        "warnings": warnings,
+    }))
+}
+
+/// Encodes a parsed design as MessagePack. Unlike JSON text it needs no
+/// number or string parsing on load, and key order is kept.
+pub fn to_binary(design: &Value) -> Result<Vec<u8>, String> {
+    rmp_serde::to_vec(design).map_err(|e| e.to_string())
+}
+
+pub fn from_binary(bytes: &[u8]) -> Result<Value, String> {
+    rmp_serde::from_slice(bytes).map_err(|e| format!("Invalid binary design: {}", e))
+}
+
+/// Writes a binary copy of a `.beaki` design for fast reopening. The JSON
+/// file stays the canonical format.
+#[tauri::command]
+pub async fn export_binary(design_path: String, output_path: String) -> Result<(), String> {
+    let text = tokio::fs::read_to_string(&design_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let design: Value =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid design JSON: {}", e))?;
+
+    fs_utils::write_atomic(Path::new(&output_path), &to_binary(&design)?)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Converts a binary design written by `export_binary` back to JSON.
+#[tauri::command]
+pub async fn import_binary(binary_path: String, output_design_path: String) -> Result<(), String> {
+    let bytes = tokio::fs::read(&binary_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let design = from_binary(&bytes)?;
+
+    let mut output = to_pretty_json(&design, "  ")?;
+    output.push('\n');
+
+    fs_utils::write_atomic(Path::new(&output_design_path), output.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_appends_new_keys_in_their_order() {
+        let existing = serde_json::json!({ "a": 1, "b": 2, "c": 3 });
+        let new = serde_json::json!({ "x": 10, "a": 1, "y": 20, "c": 30, "z": 40 });
+
+        let merged = merge_preserving_order(&existing, new);
+        assert_eq!(
+            serde_json::to_string(&merged).unwrap(),
+            r#"{"a":1,"c":30,"x":10,"y":20,"z":40}"#
+        );
+    }
+
+    #[test]
+    fn content_id_ignores_formatting_and_volatile_fields() {
+        let original = serde_json::json!({
+            "_format": "beaki-v1",
+            "document": {
+                "id": "d1",
+                "name": "Home",
+                "updatedAt": "2026-01-01T00:00:00Z",
+                "layers": { "l1": { "x": 1, "provenance": { "createdAt": "2026-01-01T00:00:00Z" } } }
+            },
+            "viewport": { "panX": 0, "panY": 0, "zoom": 1 }
+        });
+        let resaved: Value = serde_json::from_str(
+            r#"{"document": {"layers": {"l1": {"provenance": {"createdAt": "2026-02-02T00:00:00Z"}, "x": 1}},
+                "updatedAt": "2026-02-02T00:00:00Z", "name": "Home", "id": "d2"},
+                "_format": "beaki-v1", "viewport": {"panX": 40, "panY": 0, "zoom": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(content_id(&original), content_id(&resaved));
+
+        let mut edited = original.clone();
+        edited["document"]["layers"]["l1"]["x"] = serde_json::json!(2);
+        assert_ne!(content_id(&original), content_id(&edited));
+    }
+
+    #[test]
+    fn validate_beaki_requires_wrapped_document() {
+        let bare = serde_json::json!({ "id": "d1", "schemaVersion": "1.0.0", "pages": {}, "frames": {}, "layers": {} });
+        assert!(validate_beaki(&bare).is_err());
+
+        let project = serde_json::json!({ "_format": "beaki-v1", "version": "1.0.0", "document": bare });
+        assert!(validate_beaki(&project).is_ok());
+
+        let missing = serde_json::json!({ "_format": "beaki-v1", "document": { "id": "d1" } });
+        let error = validate_beaki(&missing).unwrap_err();
+        assert!(error.ends_with("schemaVersion, pages, frames, layers"), "{}", error);
+    }
+
+    #[test]
+    fn sort_keys_is_recursive_and_keeps_array_order() {
+        let value = serde_json::json!({ "b": 1, "a": { "z": [{ "y": 1, "x": 2 }, 3] } });
+        let sorted = to_pretty_json(&sort_keys(&value), "  ").unwrap();
+        assert_eq!(
+            sorted,
+            "{\n  \"a\": {\n    \"z\": [\n      {\n        \"x\": 2,\n        \"y\": 1\n      },\n      3\n    ]\n  },\n  \"b\": 1\n}"
+        );
+    }
+
+    #[test]
+    fn diff_matches_elements_by_id() {
+        let before = serde_json::json!({ "layers": [{ "id": "a", "x": 1 }, { "id": "b" }] });
+        let after = serde_json::json!({ "layers": [{ "id": "c" }, { "id": "a", "x": 2 }] });
+
+        let changes: Vec<(ChangeKind, String)> = diff_json(&before, &after)
+            .into_iter()
+            .map(|c| (c.kind, c.pointer))
+            .collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                (ChangeKind::Removed, "/layers/1".to_string()),
+                (ChangeKind::Added, "/layers/0".to_string()),
+                (ChangeKind::Changed, "/layers/1/x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_pointer_creates_and_appends() {
+        let mut doc = serde_json::json!({ "layers": [{ "name": "a" }] });
+
+        set_pointer(&mut doc, "/layers/0/name", "b".into(), false).unwrap();
+        set_pointer(&mut doc, "/layers/-", serde_json::json!({}), false).unwrap();
+        set_pointer(&mut doc, "/meta/a~1b", 1.into(), true).unwrap();
+
+        assert_eq!(
+            doc,
+            serde_json::json!({
+                "layers": [{ "name": "b" }, {}],
+                "meta": { "a/b": 1 }
+            })
+        );
+        assert!(matches!(
+            set_pointer(&mut doc, "/missing/x", 1.into(), false),
+            Err(JsonPointerError::NotFound { .. })
+        ));
+        assert!(matches!(
+            set_pointer(&mut doc, "/layers/5", 1.into(), false),
+            Err(JsonPointerError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn summary_keeps_counts_scanned_before_an_error() {
+        let summary = summarize_reader(
+            r#"{ "document": { "name": "Demo", "pages": { "p1": {}, "p2": {} }, "frames": { "f1": {"#
+                .as_bytes(),
+        );
+
+        assert_eq!(summary.name.as_deref(), Some("Demo"));
+        assert_eq!(summary.page_count, 2);
+        assert_eq!(summary.frame_count, 1);
+        assert!(summary.error.is_some());
+    }
+
+    #[test]
+    fn repair_keeps_complete_values_before_truncation() {
+        let text = r#"{ "document": { "name": "a,b}", "pages": { "p1": { "id": "p1" }, "p2": { "na"#;
+
+        let (repaired, dropped) = repair_truncated_json(text).unwrap();
+
+        assert_eq!(
+            repaired,
+            serde_json::json!({ "document": { "name": "a,b}", "pages": { "p1": { "id": "p1" } } } })
+        );
+        assert_eq!(dropped, r#", "p2": { "na"#.len());
+    }
+
+    #[test]
+    fn binary_round_trip_is_lossless() {
+        let text = r##"{
+  "version": 2,
+  "name": "Ünïcode ✓",
+  "zIndex": -3,
+  "big": 18446744073709551615,
+  "opacity": 0.1,
+  "tiny": 1e-300,
+  "visible": true,
+  "parent": null,
+  "layers": [
+    { "type": "rect", "x": 10.5, "y": -0.25, "fill": "#ff0000" },
+    { "type": "group", "children": [], "meta": {} }
+  ]
+}"##;
+        let original: Value = serde_json::from_str(text).unwrap();
+
+        let decoded = from_binary(&to_binary(&original).unwrap()).unwrap();
+
+        assert_eq!(decoded, original);
+        // Key order matters for minimal diffs of saved designs
+        assert_eq!(
+            serde_json::to_string(&decoded).unwrap(),
+            serde_json::to_string(&original).unwrap()
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Key fragments that mark a value as a secret in exported reports.
+const SECRET_KEY_HINTS: &[&str] = &["key", "token", "secret", "password", "passwd", "auth", "credential"];
+
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+/// Replaces secret-looking values in place. Every value of an `env` map is
+/// redacted regardless of its name since those commonly carry credentials.
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if key == "env" {
+                    if let Value::Object(env) = child {
+                        for env_value in env.values_mut() {
+                            *env_value = Value::String(REDACTED.to_string());
+                        }
+                        continue;
+                    }
+                }
+
+                if is_secret_key(key) && !child.is_null() && !child.is_object() {
+                    *child = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(child);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Redacts secret-looking command-line arguments: the value of a
+/// `--api-key=...` style argument, or the argument after a `--token` style flag.
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut redact_next = false;
+
+    for arg in args {
+        if std::mem::take(&mut redact_next) {
+            redacted.push(REDACTED.to_string());
+            continue;
+        }
+
+        match arg.split_once('=') {
+            Some((flag, _)) if is_secret_key(flag) => {
+                redacted.push(format!("{}={}", flag, REDACTED));
+            }
+            Some(_) => redacted.push(arg.clone()),
+            None => {
+                redact_next = arg.starts_with('-') && is_secret_key(arg);
+                redacted.push(arg.clone());
+            }
+        }
+    }
+
+    redacted
+}
+
+fn system_info() -> Value {
+    json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "family": std::env::consts::FAMILY,
+    })
+}
+
+/// Runs every integration detector concurrently and returns a pretty-printed
+/// JSON report with secrets redacted, suitable for pasting into a bug report.
+#[tauri::command]
+pub async fn export_integration_report() -> Result<String, String> {
+    let (cli_tools, mcp_servers, ollama) = futures_util::join!(
+        crate::detect_cli_tools(),
+        crate::detect_mcp_servers(None),
+        crate::ollama::detect_ollama(None),
+    );
+
+    let as_value = |result: Result<Value, String>| match result {
+        Ok(value) => value,
+        Err(e) => json!({ "error": e }),
+    };
+
+    let mut report = json!({
+        "generated_at_unix": std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        "system": system_info(),
+        "cli_tools": as_value(cli_tools.map(Value::from)),
+        "mcp_servers": as_value(mcp_servers.map(Value::from)),
+        "ollama": as_value(ollama.and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string()))),
+    });
+
+    redact_secrets(&mut report);
+
+    serde_json::to_string_pretty(&report).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct PathAccess {
+    pub path: String,
+    pub readable: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionStatus {
+    pub can_spawn: bool,
+    pub filesystem: Vec<PathAccess>,
+    pub can_reach_network: bool,
+    /// Human-readable guidance for each failed check.
+    pub messages: Vec<String>,
+}
+
+/// Looks for `needle` anywhere in an error's source chain, since reqwest keeps
+/// the underlying IO error (e.g. "connection refused") out of its own message.
+pub fn error_chain_contains(error: &dyn std::error::Error, needle: &str) -> bool {
+    let mut current = Some(error);
+    while let Some(e) = current {
+        if e.to_string().to_lowercase().contains(needle) {
+            return true;
+        }
+        current = e.source();
+    }
+    false
+}
+
+async fn check_spawn() -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = tokio::process::Command::new("cmd");
+        command.args(["/C", "exit", "0"]);
+        command
+    } else {
+        tokio::process::Command::new("true")
+    };
+
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+pub async fn check_path_access(path: &Path) -> PathAccess {
+    let readable = tokio::fs::read_dir(path).await.is_ok();
+
+    let probe = path.join(format!(".beaki-permission-probe-{}", uuid::Uuid::new_v4()));
+    let writable = tokio::fs::write(&probe, b"").await.is_ok();
+    if writable {
+        let _ = tokio::fs::remove_file(&probe).await;
+    }
+
+    PathAccess {
+        path: path.to_string_lossy().to_string(),
+        readable,
+        writable,
+    }
+}
+
+/// Checks up front whether the app can spawn processes, read and write the
+/// directories it relies on, and open network connections to Ollama, so
+/// locked-down systems get an actionable message instead of a cryptic
+/// failure later.
+#[tauri::command]
+pub async fn check_permissions() -> Result<PermissionStatus, String> {
+    let mut messages = Vec::new();
+
+    let can_spawn = match check_spawn().await {
+        Ok(()) => true,
+        Err(e) => {
+            messages.push(format!(
+                "Cannot start child processes ({}). MCP servers and CLI agents will not work; check sandboxing or security software settings.",
+                e
+            ));
+            false
+        }
+    };
+
+    let mut roots = Vec::new();
+    if let Some(documents) = dirs::document_dir() {
+        roots.push(documents);
+    }
+    if let Ok(settings_dir) = crate::settings::settings_dir() {
+        if tokio::fs::create_dir_all(&settings_dir).await.is_ok() {
+            roots.push(settings_dir);
+        }
+    }
+
+    let mut filesystem = Vec::new();
+    for root in roots {
+        let access = check_path_access(&root).await;
+        if !access.readable || !access.writable {
+            messages.push(format!(
+                "No {} access to {}. Grant the app file access (on macOS: System Settings > Privacy & Security > Files and Folders).",
+                if access.readable { "write" } else { "read" },
+                access.path
+            ));
+        }
+        filesystem.push(access);
+    }
+
+    let host = crate::ollama::resolve_host(None).await;
+    let can_reach_network = match reqwest::Client::new()
+        .get(&host)
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+    {
+        Ok(_) => true,
+        // A refused connection still proves sockets can be opened; only
+        // other failures point at a permission or firewall problem.
+        Err(e) if e.is_connect() && error_chain_contains(&e, "refused") => true,
+        Err(e) => {
+            messages.push(format!(
+                "Cannot open a connection to {} ({}). Check firewall or local network permissions.",
+                host, e
+            ));
+            false
+        }
+    };
+
+    Ok(PermissionStatus {
+        can_spawn,
+        filesystem,
+        can_reach_network,
+        messages,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortStatus {
+    pub port: u16,
+    pub available: bool,
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+}
+
+/// Finds the process listening on a TCP port by asking the platform's own
+/// tooling (`lsof` or `netstat`/`tasklist`). Best effort: `None` when the
+/// tools are missing or the owner can't be determined.
+async fn find_port_owner(port: u16) -> Option<(Option<String>, Option<u32>)> {
+    if cfg!(target_os = "windows") {
+        let output = tokio::process::Command::new("netstat")
+            .args(["-ano", "-p", "TCP"])
+            .output()
+            .await
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let suffix = format!(":{}", port);
+
+        let pid = text.lines().find_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            match columns.as_slice() {
+                [_, local, _, state, pid] if local.ends_with(&suffix) && *state == "LISTENING" => {
+                    pid.parse::<u32>().ok()
+                }
+                _ => None,
+            }
+        })?;
+
+        let output = tokio::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .output()
+            .await
+            .ok();
+        let name = output.and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .split(',')
+                .next()
+                .map(|n| n.trim_matches('"').to_string())
+                .filter(|n| !n.is_empty() && !n.starts_with("INFO:"))
+        });
+
+        Some((name, Some(pid)))
+    } else {
+        let output = tokio::process::Command::new("lsof")
+            .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+            .output()
+            .await
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+
+        let pid = text
+            .lines()
+            .find_map(|l| l.strip_prefix('p'))
+            .and_then(|p| p.parse::<u32>().ok());
+        let name = text
+            .lines()
+            .find_map(|l| l.strip_prefix('c'))
+            .map(|n| n.to_string());
+
+        if pid.is_none() && name.is_none() {
+            None
+        } else {
+            Some((name, pid))
+        }
+    }
+}
+
+/// Probes whether a local TCP port is free by binding it, and looks up the
+/// owning process when it isn't.
+pub async fn port_status(port: u16) -> PortStatus {
+    let available = tokio::net::TcpListener::bind(("127.0.0.1", port)).await.is_ok();
+
+    let (process_name, pid) = if available {
+        (None, None)
+    } else {
+        find_port_owner(port).await.unwrap_or((None, None))
+    };
+
+    PortStatus {
+        port,
+        available,
+        process_name,
+        pid,
+    }
+}
+
+#[tauri::command]
+pub async fn check_port(port: u16) -> Result<PortStatus, String> {
+    Ok(port_status(port).await)
+}
+
+/// Largest file `benchmark_io` will write.
+const MAX_BENCHMARK_BYTES: u64 = 1024 * 1024 * 1024;
+const BENCHMARK_CHUNK_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct IoBenchmark {
+    pub size_bytes: u64,
+    pub write_mbps: f64,
+    /// Usually served from the OS page cache right after the write, so this
+    /// is an upper bound on cold read speed.
+    pub read_mbps: f64,
+}
+
+fn megabytes_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+async fn run_io_benchmark(file_path: &Path, size_bytes: u64) -> std::io::Result<IoBenchmark> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let chunk = vec![0xA5u8; BENCHMARK_CHUNK_BYTES];
+
+    let started = std::time::Instant::now();
+    let mut file = tokio::fs::File::create(file_path).await?;
+    let mut remaining = size_bytes;
+    while remaining > 0 {
+        let len = remaining.min(chunk.len() as u64) as usize;
+        file.write_all(&chunk[..len]).await?;
+        remaining -= len as u64;
+    }
+    // Count the time to reach the disk, not just the page cache
+    file.sync_all().await?;
+    drop(file);
+    let write_elapsed = started.elapsed();
+
+    let started = std::time::Instant::now();
+    let mut file = tokio::fs::File::open(file_path).await?;
+    let mut buffer = vec![0u8; BENCHMARK_CHUNK_BYTES];
+    while file.read(&mut buffer).await? > 0 {}
+    let read_elapsed = started.elapsed();
+
+    Ok(IoBenchmark {
+        size_bytes,
+        write_mbps: megabytes_per_sec(size_bytes, write_elapsed),
+        read_mbps: megabytes_per_sec(size_bytes, read_elapsed),
+    })
+}
+
+/// Measures write and read throughput of a directory by writing a temporary
+/// file of `size_bytes` into it, reading it back and deleting it, so slow
+/// save locations such as network drives can be spotted.
+#[tauri::command]
+pub async fn benchmark_io(path: String, size_bytes: u64) -> Result<IoBenchmark, String> {
+    if size_bytes == 0 || size_bytes > MAX_BENCHMARK_BYTES {
+        return Err(format!("size_bytes must be between 1 and {}", MAX_BENCHMARK_BYTES));
+    }
+
+    let file_path = Path::new(&path).join(format!(".beaki-benchmark-{}", uuid::Uuid::new_v4()));
+    let result = run_io_benchmark(&file_path, size_bytes).await;
+    let _ = tokio::fs::remove_file(&file_path).await;
+
+    result.map_err(|e| format!("I/O benchmark in {} failed: {}", path, e))
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvLineError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct EnvFile {
+    pub vars: HashMap<String, String>,
+    /// Lines that couldn't be parsed; every other line is still applied.
+    pub errors: Vec<EnvLineError>,
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Parses the part after `=`. Double-quoted values support `\n`, `\r`, `\t`,
+/// `\"` and `\\` escapes; single-quoted values are literal; unquoted values
+/// end at a ` #` comment.
+fn parse_value(raw: &str) -> Result<String, String> {
+    let raw = raw.trim_start();
+
+    let (value, rest) = match raw.chars().next() {
+        Some('"') => {
+            let mut value = String::new();
+            let mut chars = raw[1..].char_indices();
+            let mut end = None;
+
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        end = Some(i + 2);
+                        break;
+                    }
+                    '\\' => match chars.next().map(|(_, e)| e) {
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some('t') => value.push('\t'),
+                        Some(e @ ('"' | '\\')) => value.push(e),
+                        Some(e) => {
+                            value.push('\\');
+                            value.push(e);
+                        }
+                        None => value.push('\\'),
+                    },
+                    c => value.push(c),
+                }
+            }
+
+            let end = end.ok_or_else(|| "Unterminated double-quoted value".to_string())?;
+            (value, &raw[end..])
+        }
+        Some('\'') => {
+            let close = raw[1..]
+                .find('\'')
+                .ok_or_else(|| "Unterminated single-quoted value".to_string())?;
+            (raw[1..close + 1].to_string(), &raw[close + 2..])
+        }
+        _ => {
+            let value = match raw.find(" #").or_else(|| raw.find("\t#")) {
+                Some(comment) => &raw[..comment],
+                None => raw,
+            };
+            return Ok(value.trim_end().to_string());
+        }
+    };
+
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("Unexpected text after quoted value: '{}'", rest));
+    }
+
+    Ok(value)
+}
+
+/// Parses dotenv syntax: `KEY=value` lines with optional `export ` prefixes,
+/// quoted values and `#` comments. Later duplicates win.
+pub fn parse_env(text: &str) -> EnvFile {
+    let mut env = EnvFile::default();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+
+        let result = line
+            .split_once('=')
+            .ok_or_else(|| "Expected KEY=value".to_string())
+            .and_then(|(key, raw)| {
+                let key = key.trim();
+                if !is_valid_key(key) {
+                    return Err(format!("Invalid variable name '{}'", key));
+                }
+                Ok((key.to_string(), parse_value(raw)?))
+            });
+
+        match result {
+            Ok((key, value)) => {
+                env.vars.insert(key, value);
+            }
+            Err(message) => env.errors.push(EnvLineError {
+                line: index + 1,
+                message,
+            }),
+        }
+    }
+
+    env
+}
+
+/// Reads and parses a `.env` file. A missing file yields no variables.
+pub async fn load_env_file(path: &str) -> Result<EnvFile, String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(text) => Ok(parse_env(&text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(EnvFile::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+#[tauri::command]
+pub async fn read_env_file(path: String) -> Result<EnvFile, String> {
+    load_env_file(&path).await
+}
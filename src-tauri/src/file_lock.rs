@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::fs_utils;
+
+/// Directories not searched for stale locks: nothing the app opens lives there.
+const EXCLUDED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+/// Contents of a `{path}.lock` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    /// When the lock was taken, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+fn lock_path(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.lock", path))
+}
+
+#[cfg(unix)]
+fn pid_is_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    // EPERM means it exists but belongs to another user
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn pid_is_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    /// Exit code reported for a process that hasn't exited.
+    const STILL_ACTIVE: u32 = 259;
+
+    // SAFETY: the handle is checked before use and closed exactly once
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle == 0 {
+            return false;
+        }
+        let mut code = 0u32;
+        let ok = GetExitCodeProcess(handle, &mut code);
+        CloseHandle(handle);
+        ok != 0 && code == STILL_ACTIVE
+    }
+}
+
+/// Reads the lock for `path`, deleting it if its owner is no longer running.
+/// Returns the lock only while it's live.
+async fn live_lock(path: &str) -> Result<Option<LockInfo>, String> {
+    let lock_file = lock_path(path);
+
+    let content = match tokio::fs::read_to_string(&lock_file).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {:?}: {}", lock_file, e)),
+    };
+
+    // An unreadable lock can't name a live owner, so treat it as stale
+    match serde_json::from_str::<LockInfo>(&content) {
+        Ok(lock) if pid_is_alive(lock.pid) => Ok(Some(lock)),
+        _ => {
+            let _ = tokio::fs::remove_file(&lock_file).await;
+            Ok(None)
+        }
+    }
+}
+
+/// Whether another running app instance holds the lock on `path`. Stale
+/// locks left by instances that have exited are cleaned up.
+#[tauri::command]
+pub async fn is_file_open_elsewhere(path: String) -> Result<bool, String> {
+    Ok(live_lock(&path)
+        .await?
+        .map(|lock| lock.pid != std::process::id())
+        .unwrap_or(false))
+}
+
+/// Takes the lock on a design by writing `{path}.lock` with our PID. Fails if
+/// another live instance already holds it; re-locking our own file is fine.
+#[tauri::command]
+pub async fn lock_file(path: String) -> Result<(), String> {
+    if let Some(lock) = live_lock(&path).await? {
+        if lock.pid == std::process::id() {
+            return Ok(());
+        }
+        return Err(format!("'{}' is open in another instance (PID {})", path, lock.pid));
+    }
+
+    let lock = LockInfo {
+        pid: std::process::id(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    let json = serde_json::to_string(&lock).map_err(|e| e.to_string())?;
+
+    // `create_new` makes two instances racing for the same file fail safely
+    let lock_file = lock_path(&path);
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_file)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("'{}' was just opened by another instance", path),
+            _ => format!("Failed to create {:?}: {}", lock_file, e),
+        })?;
+
+    file.write_all(json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", lock_file, e))
+}
+
+/// Releases our lock on a design. Locks held by other instances are left
+/// alone. Returns whether a lock was removed.
+#[tauri::command]
+pub async fn unlock_file(path: String) -> Result<bool, String> {
+    match live_lock(&path).await? {
+        Some(lock) if lock.pid == std::process::id() => {
+            tokio::fs::remove_file(lock_path(&path))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Where designs are saved by default, `Documents/Beak Designs`.
+pub fn default_designs_dir() -> Option<PathBuf> {
+    dirs::document_dir().map(|documents| documents.join("Beak Designs"))
+}
+
+/// Removes `.lock` files under `root` whose owning process has exited.
+/// Files that don't parse as our locks (`Cargo.lock`, `yarn.lock`, ...) are
+/// never touched. Runs synchronously; returns the removed lock paths.
+pub fn remove_stale_locks(root: &Path) -> std::io::Result<Vec<String>> {
+    let exclude = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| EXCLUDED_DIRS.contains(&name))
+            .unwrap_or(false)
+    };
+    let walk = fs_utils::walk_files(root, &exclude)?;
+
+    let mut removed = Vec::new();
+    for path in walk.files {
+        if path.extension().and_then(|e| e.to_str()) != Some("lock") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(lock) = serde_json::from_str::<LockInfo>(&content) else {
+            continue;
+        };
+        if !pid_is_alive(lock.pid) && std::fs::remove_file(&path).is_ok() {
+            removed.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    removed.sort();
+    Ok(removed)
+}
+
+/// Cleans up locks left under `root` by instances that crashed, so the
+/// designs they held can be opened again. Returns the removed lock paths.
+#[tauri::command]
+pub async fn clean_stale_locks(root: String) -> Result<Vec<String>, String> {
+    let scan_root = root.clone();
+    tokio::task::spawn_blocking(move || remove_stale_locks(Path::new(&scan_root)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to scan '{}' for locks: {}", root, e))
+}
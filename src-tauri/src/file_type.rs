@@ -0,0 +1,149 @@
+use serde::Serialize;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// How much of a file is read for magic-byte sniffing.
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// Common asset types by extension, for files without a recognizable
+/// signature (text formats, mostly).
+const EXTENSION_MIME_TYPES: &[(&str, &str)] = &[
+    ("beaki", "application/json"),
+    ("json", "application/json"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("ico", "image/x-icon"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("xml", "application/xml"),
+];
+
+pub fn mime_from_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSION_MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// Reads the first bytes of a file for sniffing.
+pub async fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut head = Vec::new();
+    file.take(SNIFF_BYTES).read_to_end(&mut head).await?;
+    Ok(head)
+}
+
+/// Guesses a MIME type from the leading bytes, falling back to the
+/// extension when no signature matches.
+pub fn guess_mime(path: &Path, head: &[u8]) -> Option<String> {
+    infer::get(head)
+        .map(|kind| kind.mime_type().to_string())
+        .or_else(|| mime_from_extension(path).map(String::from))
+}
+
+/// Heuristic binary check on a file's leading bytes: NUL bytes or invalid
+/// UTF-8 (a multi-byte character cut off at the end of the sample is fine).
+pub fn looks_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some() || (head.len() as u64) < SNIFF_BYTES,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileType {
+    pub mime: Option<String>,
+    /// Conventional extension for the detected type, without the dot. From
+    /// the signature when one matched, else the file's own extension.
+    pub extension_guess: Option<String>,
+    pub is_binary: bool,
+}
+
+/// Identifies a file by its magic bytes (images, fonts, archives, PDFs,
+/// audio/video, ...), falling back to its extension when the signature is
+/// inconclusive, as it is for text formats.
+#[tauri::command]
+pub async fn detect_file_type(path: String) -> Result<FileType, String> {
+    let path = Path::new(&path);
+    let head = read_head(path).await.map_err(|e| e.to_string())?;
+
+    if let Some(kind) = infer::get(&head) {
+        return Ok(FileType {
+            mime: Some(kind.mime_type().to_string()),
+            extension_guess: Some(kind.extension().to_string()),
+            is_binary: kind.matcher_type() != infer::MatcherType::Text || looks_binary(&head),
+        });
+    }
+
+    Ok(FileType {
+        mime: mime_from_extension(path).map(String::from),
+        extension_guess: path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase()),
+        is_binary: looks_binary(&head),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct DroppedPathInfo {
+    pub path: String,
+    pub exists: bool,
+    pub is_dir: bool,
+    /// File size in bytes; `None` for directories and missing paths.
+    pub size: Option<u64>,
+    /// Guessed MIME type of a file, from magic bytes or the extension.
+    pub mime: Option<String>,
+}
+
+async fn inspect_path(path: String) -> DroppedPathInfo {
+    let mut info = DroppedPathInfo {
+        exists: false,
+        is_dir: false,
+        size: None,
+        mime: None,
+        path,
+    };
+
+    let Ok(metadata) = tokio::fs::metadata(&info.path).await else {
+        return info;
+    };
+    info.exists = true;
+    info.is_dir = metadata.is_dir();
+    if info.is_dir {
+        return info;
+    }
+
+    info.size = Some(metadata.len());
+    let path = Path::new(&info.path);
+    info.mime = match read_head(path).await {
+        Ok(head) => guess_mime(path, &head),
+        Err(_) => mime_from_extension(path).map(String::from),
+    };
+    info
+}
+
+/// Inspects paths dropped onto the app, concurrently, so the drop handler
+/// can decide how to import each one. Results are in input order.
+#[tauri::command]
+pub async fn inspect_dropped_paths(paths: Vec<String>) -> Result<Vec<DroppedPathInfo>, String> {
+    Ok(futures_util::future::join_all(paths.into_iter().map(inspect_path)).await)
+}
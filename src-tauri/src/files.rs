@@ -0,0 +1,811 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+
+use crate::fs_utils;
+use crate::hashing::{HashAlgorithm, Hasher};
+use crate::requests::{self, RequestMap};
+
+/// Number of entries emitted per `dir-entries` event.
+const DIRECTORY_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+struct DirEntriesPayload {
+    request_id: String,
+    batch: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DirEntriesDonePayload {
+    request_id: String,
+    total: usize,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+async fn emit_directory_batches(
+    app: &AppHandle,
+    path: &str,
+    request_id: &str,
+    flag: &requests::CancelFlag,
+) -> Result<(usize, bool), String> {
+    let mut dir = tokio::fs::read_dir(path).await.map_err(|e| e.to_string())?;
+    let mut batch = Vec::with_capacity(DIRECTORY_BATCH_SIZE);
+    let mut total = 0;
+
+    while let Some(entry) = dir.next_entry().await.map_err(|e| e.to_string())? {
+        if flag.is_cancelled() {
+            return Ok((total, true));
+        }
+
+        batch.push(entry.path().to_string_lossy().to_string());
+        total += 1;
+
+        if batch.len() == DIRECTORY_BATCH_SIZE {
+            let _ = app.emit(
+                "dir-entries",
+                DirEntriesPayload {
+                    request_id: request_id.to_string(),
+                    batch: std::mem::take(&mut batch),
+                },
+            );
+        }
+    }
+
+    if !batch.is_empty() {
+        let _ = app.emit(
+            "dir-entries",
+            DirEntriesPayload {
+                request_id: request_id.to_string(),
+                batch,
+            },
+        );
+    }
+
+    Ok((total, false))
+}
+
+/// Lists a directory incrementally, emitting `dir-entries` events in batches
+/// and a final `dir-entries-done` event. Cancel with `cancel_request`.
+#[tauri::command]
+pub async fn stream_directory(
+    path: String,
+    request_id: String,
+    app: AppHandle,
+    state: tauri::State<'_, RequestMap>,
+) -> Result<(), String> {
+    let flag = requests::register(&state, &request_id).await?;
+
+    let result = emit_directory_batches(&app, &path, &request_id, &flag).await;
+    requests::finish(&state, &request_id).await;
+
+    let done = match &result {
+        Ok((total, cancelled)) => DirEntriesDonePayload {
+            request_id: request_id.clone(),
+            total: *total,
+            cancelled: *cancelled,
+            error: None,
+        },
+        Err(e) => DirEntriesDonePayload {
+            request_id: request_id.clone(),
+            total: 0,
+            cancelled: false,
+            error: Some(e.clone()),
+        },
+    };
+    let _ = app.emit("dir-entries-done", done);
+
+    result.map(|_| ())
+}
+
+/// Pre-flight check for recursive operations: reports whether following
+/// symlinks under `path` would loop back into one of its own ancestors.
+#[tauri::command]
+pub async fn has_symlink_cycle(path: String) -> Result<bool, String> {
+    tokio::task::spawn_blocking(move || {
+        fs_utils::walk_files(Path::new(&path), &|_| false)
+            .map(|outcome| outcome.has_cycle)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+pub struct HashedContents {
+    pub contents: String,
+    pub hash: String,
+}
+
+/// Reads a text file and hashes it in the same pass, so verifying a large
+/// design doesn't read it from disk twice. `algorithm` is `sha256` (default)
+/// or `blake3`.
+#[tauri::command]
+pub async fn read_and_hash_file(
+    path: String,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<HashedContents, String> {
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let capacity = file
+        .metadata()
+        .await
+        .map(|m| m.len() as usize)
+        .unwrap_or(0);
+
+    let mut hasher = Hasher::new(algorithm.unwrap_or_default());
+    let mut bytes = Vec::with_capacity(capacity);
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        bytes.extend_from_slice(&buffer[..read]);
+    }
+
+    let contents = String::from_utf8(bytes)
+        .map_err(|e| format!("File is not valid UTF-8: {}", e))?;
+
+    Ok(HashedContents {
+        contents,
+        hash: hasher.finalize_hex(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum ConditionalWriteError {
+    /// The file changed since it was read. `current_hash` is `None` when it
+    /// no longer exists.
+    Conflict { current_hash: Option<String> },
+    Io { message: String },
+}
+
+/// Compare-and-swap save: writes `contents` atomically only if the file's
+/// current hash equals `expected_hash` (as returned by `read_and_hash_file`
+/// with the same algorithm). Returns the hash of the new contents for the
+/// next save. Guards against external edits, not against two concurrent
+/// saves racing between the check and the rename.
+#[tauri::command]
+pub async fn write_file_if_unchanged(
+    path: String,
+    contents: String,
+    expected_hash: String,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<String, ConditionalWriteError> {
+    let algorithm = algorithm.unwrap_or_default();
+    let io_error = |e: std::io::Error| ConditionalWriteError::Io {
+        message: e.to_string(),
+    };
+
+    let current = match tokio::fs::read(&path).await {
+        Ok(bytes) => Some(crate::hashing::hash_bytes(algorithm, &bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(io_error(e)),
+    };
+    if !current
+        .as_deref()
+        .is_some_and(|hash| hash.eq_ignore_ascii_case(&expected_hash))
+    {
+        return Err(ConditionalWriteError::Conflict {
+            current_hash: current,
+        });
+    }
+
+    fs_utils::write_atomic(Path::new(&path), contents.as_bytes())
+        .await
+        .map_err(io_error)?;
+
+    Ok(crate::hashing::hash_bytes(algorithm, contents.as_bytes()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMetadata {
+    pub size: u64,
+    /// Last modification time in milliseconds since the Unix epoch.
+    pub modified_ms: Option<u64>,
+    pub readonly: bool,
+}
+
+impl From<&std::fs::Metadata> for FileMetadata {
+    fn from(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified_ms: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64),
+            readonly: metadata.permissions().readonly(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenedFile {
+    pub contents: String,
+    pub metadata: FileMetadata,
+}
+
+/// Reads a text file together with its metadata. Both come from the same open
+/// handle, so a file replaced by path in between can't pair one file's
+/// contents with another's mtime.
+#[tauri::command]
+pub async fn open_file(path: String) -> Result<OpenedFile, String> {
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let metadata = file.metadata().await.map_err(|e| e.to_string())?;
+
+    let mut bytes = Vec::with_capacity(metadata.len() as usize);
+    file.read_to_end(&mut bytes)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let contents = String::from_utf8(bytes)
+        .map_err(|e| format!("File is not valid UTF-8: {}", e))?;
+
+    Ok(OpenedFile {
+        contents,
+        metadata: FileMetadata::from(&metadata),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileContext {
+    pub text: String,
+    /// Byte offset of the first byte of `text` in the file.
+    pub start_offset: u64,
+    /// 1-based line of the requested offset.
+    pub line: u64,
+    /// 1-based byte column of the requested offset, as serde_json reports.
+    pub column: u64,
+}
+
+/// Counts the lines before `offset`, returning the offset's 1-based line and
+/// the byte offset at which that line starts.
+async fn line_start_before(file: &mut tokio::fs::File, offset: u64) -> std::io::Result<(u64, u64)> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut position = 0u64;
+    let mut line = 1u64;
+    let mut line_start = 0u64;
+
+    while position < offset {
+        let want = buffer.len().min((offset - position) as usize);
+        let read = file.read(&mut buffer[..want]).await?;
+        if read == 0 {
+            break;
+        }
+        for (i, byte) in buffer[..read].iter().enumerate() {
+            if *byte == b'\n' {
+                line += 1;
+                line_start = position + i as u64 + 1;
+            }
+        }
+        position += read as u64;
+    }
+
+    Ok((line, line_start))
+}
+
+/// Trims partial UTF-8 characters cut off at either end of a window. Returns
+/// the text and how many leading bytes were skipped.
+fn window_text(bytes: &[u8]) -> (String, usize) {
+    let skip = bytes
+        .iter()
+        .take(3)
+        .take_while(|b| (0x80..0xC0).contains(*b))
+        .count();
+    let bytes = &bytes[skip..];
+    let end = match std::str::from_utf8(bytes) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => bytes.len(),
+    };
+    (String::from_utf8_lossy(&bytes[..end]).into_owned(), skip)
+}
+
+/// Reads up to `context_bytes` either side of `offset` along with the
+/// offset's line and column, for showing where a parse error points.
+#[tauri::command]
+pub async fn read_file_context(path: String, offset: u64, context_bytes: u64) -> Result<FileContext, String> {
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let len = file.metadata().await.map_err(|e| e.to_string())?.len();
+    if offset > len {
+        return Err(format!("Offset {} is past the end of {} ({} bytes)", offset, path, len));
+    }
+
+    let (line, line_start) = line_start_before(&mut file, offset)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let start = offset.saturating_sub(context_bytes);
+    let end = offset.saturating_add(context_bytes).min(len);
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut window = Vec::with_capacity((end - start) as usize);
+    file.take(end - start)
+        .read_to_end(&mut window)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (text, skipped) = window_text(&window);
+    Ok(FileContext {
+        text,
+        start_offset: start + skipped as u64,
+        line,
+        column: offset - line_start + 1,
+    })
+}
+
+/// Returns the last `lines` lines of a file, e.g. one a process's output is
+/// redirected to.
+#[tauri::command]
+pub async fn tail_file(path: String, lines: usize) -> Result<Vec<String>, String> {
+    fs_utils::tail_lines(Path::new(&path), lines)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// Every this many lines the line index records a byte offset, so a page is
+/// found with one seek and at most this many skipped lines.
+const LINE_INDEX_STRIDE: u64 = 1024;
+
+/// Sparse line index of one file version: `checkpoints[i]` is the byte
+/// offset where line `i * LINE_INDEX_STRIDE` starts.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+    total_lines: u64,
+    checkpoints: Vec<u64>,
+}
+
+/// Line indexes by path, reused while the file's mtime and size are unchanged.
+pub type LineIndexCache = Arc<Mutex<HashMap<PathBuf, LineIndex>>>;
+
+pub fn create_line_index_cache() -> LineIndexCache {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+async fn build_line_index(file: &mut tokio::fs::File, metadata: &std::fs::Metadata) -> std::io::Result<LineIndex> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut position = 0u64;
+    let mut lines = 0u64;
+    let mut checkpoints = vec![0];
+    let mut last_byte = None;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        for (i, byte) in buffer[..read].iter().enumerate() {
+            if *byte == b'\n' {
+                lines += 1;
+                if lines % LINE_INDEX_STRIDE == 0 {
+                    checkpoints.push(position + i as u64 + 1);
+                }
+            }
+        }
+        position += read as u64;
+        last_byte = Some(buffer[read - 1]);
+    }
+
+    if matches!(last_byte, Some(b) if b != b'\n') {
+        lines += 1;
+    }
+
+    Ok(LineIndex {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+        total_lines: lines,
+        checkpoints,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileLines {
+    pub lines: Vec<String>,
+    pub total_lines: u64,
+    pub has_more: bool,
+}
+
+/// Reads `count` lines starting at 0-based `start_line`, for paging through
+/// large logs. The first read of a file indexes its line offsets; later pages
+/// of the same unchanged file seek straight to the nearest indexed line.
+#[tauri::command]
+pub async fn read_file_lines(
+    path: String,
+    start_line: u64,
+    count: usize,
+    cache: tauri::State<'_, LineIndexCache>,
+) -> Result<FileLines, String> {
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let metadata = file.metadata().await.map_err(|e| e.to_string())?;
+    let key = PathBuf::from(&path);
+
+    let cached = cache.lock().await.get(&key).cloned();
+    let index = match cached {
+        Some(index) if index.len == metadata.len() && index.modified == metadata.modified().ok() => index,
+        _ => {
+            let index = build_line_index(&mut file, &metadata)
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            cache.lock().await.insert(key, index.clone());
+            index
+        }
+    };
+
+    let mut lines = Vec::new();
+    if start_line < index.total_lines && count > 0 {
+        let checkpoint = (start_line / LINE_INDEX_STRIDE) as usize;
+        file.seek(SeekFrom::Start(index.checkpoints[checkpoint]))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut reader = tokio::io::BufReader::new(file);
+        let mut line = Vec::new();
+        let mut current = checkpoint as u64 * LINE_INDEX_STRIDE;
+        while lines.len() < count {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).await.map_err(|e| e.to_string())? == 0 {
+                break;
+            }
+            if current >= start_line {
+                let text = String::from_utf8_lossy(&line);
+                lines.push(text.trim_end_matches(['\r', '\n']).to_string());
+            }
+            current += 1;
+        }
+    }
+
+    Ok(FileLines {
+        has_more: start_line + (lines.len() as u64) < index.total_lines,
+        lines,
+        total_lines: index.total_lines,
+    })
+}
+
+/// `root`-relative path with `/` separators, as glob patterns are written.
+fn glob_key(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn build_globset(patterns: &[String]) -> Result<globset::GlobSet, String> {
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = globset::GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Files under `root` whose root-relative path matches `pattern` (`*` stays
+/// within one directory, `**` crosses them, so `assets/**/*.svg`). Entries
+/// matching any of `excludes` are skipped, directories without being
+/// entered. Symlinks are followed but no directory is walked twice. Returns
+/// absolute paths, sorted.
+#[tauri::command]
+pub async fn glob_files(
+    root: String,
+    pattern: String,
+    excludes: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let matcher = build_globset(std::slice::from_ref(&pattern))?;
+    let excludes = build_globset(&excludes.unwrap_or_default())?;
+    let root = PathBuf::from(root);
+
+    let walk = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || {
+            fs_utils::walk_files(&root, &|path: &Path| excludes.is_match(glob_key(&root, path)))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read {}: {}", root.display(), e))?
+    };
+
+    let mut matches: Vec<String> = walk
+        .files
+        .iter()
+        .filter(|path| matcher.is_match(glob_key(&root, path)))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    matches.sort();
+    Ok(matches)
+}
+
+/// Bounds a file to `max_bytes`. Trimming from the end shortens it in place;
+/// trimming from the start keeps the newest `max_bytes` and rewrites the file
+/// atomically. Returns `false` when the file was already small enough.
+#[tauri::command]
+pub async fn truncate_file(path: String, max_bytes: u64, from_start: bool) -> Result<bool, String> {
+    let len = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    if len <= max_bytes {
+        return Ok(false);
+    }
+
+    if from_start {
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| e.to_string())?;
+        file.seek(SeekFrom::Start(len - max_bytes))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut kept = Vec::with_capacity(max_bytes as usize);
+        file.read_to_end(&mut kept)
+            .await
+            .map_err(|e| e.to_string())?;
+        drop(file);
+
+        fs_utils::write_atomic(Path::new(&path), &kept)
+            .await
+            .map_err(|e| e.to_string())?;
+    } else {
+        let file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .await
+            .map_err(|e| e.to_string())?;
+        file.set_len(max_bytes).await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(true)
+}
+
+/// File whose presence marks a directory as an existing project.
+pub const PROJECT_MARKER_FILE: &str = ".beaki-project";
+
+#[derive(Debug, Serialize)]
+pub struct ProjectDirStatus {
+    pub exists: bool,
+    pub is_dir: bool,
+    pub writable: bool,
+    pub has_existing_project: bool,
+}
+
+/// Checks that a directory can hold a new project: it exists, is a directory,
+/// accepts writes, and whether it already contains one.
+#[tauri::command]
+pub async fn validate_project_dir(path: String) -> Result<ProjectDirStatus, String> {
+    let dir = Path::new(&path);
+
+    let metadata = match tokio::fs::metadata(dir).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(ProjectDirStatus {
+                exists: false,
+                is_dir: false,
+                writable: false,
+                has_existing_project: false,
+            });
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if !metadata.is_dir() {
+        return Ok(ProjectDirStatus {
+            exists: true,
+            is_dir: false,
+            writable: false,
+            has_existing_project: false,
+        });
+    }
+
+    let access = crate::diagnostics::check_path_access(dir).await;
+    let has_existing_project = tokio::fs::try_exists(dir.join(PROJECT_MARKER_FILE))
+        .await
+        .unwrap_or(false);
+
+    Ok(ProjectDirStatus {
+        exists: true,
+        is_dir: true,
+        writable: access.writable,
+        has_existing_project,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum PathError {
+    /// Nothing exists at the path.
+    NotFound { path: String },
+    /// The path is a symlink (or goes through one) whose target is missing.
+    BrokenSymlink { path: String, target: Option<String> },
+    /// Symlinks point at each other in a loop.
+    SymlinkLoop { path: String },
+    Io { path: String, message: String },
+}
+
+fn is_symlink_loop(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        error.raw_os_error() == Some(libc::ELOOP)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+/// Drops the `\\?\` prefix Windows adds to canonical paths when the plain
+/// form refers to the same drive path.
+fn display_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => rest.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Resolves every symlink in `path` and normalizes it to an absolute path.
+#[tauri::command]
+pub async fn canonicalize_path(path: String) -> Result<String, PathError> {
+    match tokio::fs::canonicalize(&path).await {
+        Ok(resolved) => Ok(display_path(&resolved)),
+        Err(e) if is_symlink_loop(&e) => Err(PathError::SymlinkLoop { path }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // The link itself existing means something in its chain dangles
+            match tokio::fs::symlink_metadata(&path).await {
+                Ok(_) => {
+                    let target = tokio::fs::read_link(&path)
+                        .await
+                        .ok()
+                        .map(|t| t.to_string_lossy().to_string());
+                    Err(PathError::BrokenSymlink { path, target })
+                }
+                Err(_) => Err(PathError::NotFound { path }),
+            }
+        }
+        Err(e) => Err(PathError::Io {
+            path,
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Adds the execute bit wherever the file is readable, like `chmod +x`.
+/// Used to recover from a `NotExecutable` spawn error.
+#[tauri::command]
+pub async fn make_executable(path: String) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mode = metadata.permissions().mode();
+        let executable = mode | ((mode & 0o444) >> 2);
+
+        tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(executable))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Err("Execute permissions only exist on Unix".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_temp_directory() -> Result<String, String> {
+    let dir = fs_utils::app_temp_dir();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Age after which leftover temp files are removed at startup.
+pub const STALE_TEMP_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Default, Serialize)]
+pub struct TempCleanup {
+    pub removed: Vec<String>,
+    pub bytes_freed: u64,
+}
+
+fn remove_if_stale(path: &Path, max_age: std::time::Duration, cleanup: &mut TempCleanup) {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return;
+    };
+    let stale = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= max_age);
+    if !stale {
+        return;
+    }
+
+    let removed = if metadata.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    if removed.is_ok() {
+        cleanup.bytes_freed += metadata.len();
+        cleanup.removed.push(path.to_string_lossy().to_string());
+    }
+}
+
+/// Removes temp files older than `max_age`: everything in the app temp
+/// directory, plus atomic-write and benchmark leftovers in the app's own
+/// directories and `extra_dirs` (not recursive). Runs synchronously.
+pub fn cleanup_stale_temp_files(max_age: std::time::Duration, extra_dirs: &[PathBuf]) -> TempCleanup {
+    let mut cleanup = TempCleanup::default();
+
+    if let Ok(entries) = std::fs::read_dir(fs_utils::app_temp_dir()) {
+        for entry in entries.flatten() {
+            remove_if_stale(&entry.path(), max_age, &mut cleanup);
+        }
+    }
+
+    let app_dirs = [
+        crate::settings::settings_dir().ok(),
+        crate::sessions::sessions_dir().ok(),
+        crate::settings::cache_dir().ok(),
+    ];
+    for dir in app_dirs.into_iter().flatten().chain(extra_dirs.iter().cloned()) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if fs_utils::is_app_temp_file(&entry.file_name().to_string_lossy()) {
+                remove_if_stale(&entry.path(), max_age, &mut cleanup);
+            }
+        }
+    }
+
+    cleanup
+}
+
+/// Removes temp files left behind by interrupted operations. Pass project
+/// directories as `extra_dirs` to also clean atomic-save leftovers there.
+#[tauri::command]
+pub async fn cleanup_temp_files(
+    older_than_secs: u64,
+    extra_dirs: Option<Vec<String>>,
+) -> Result<TempCleanup, String> {
+    let extra_dirs: Vec<PathBuf> = extra_dirs
+        .unwrap_or_default()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    tokio::task::spawn_blocking(move || {
+        cleanup_stale_temp_files(std::time::Duration::from_secs(older_than_secs), &extra_dirs)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
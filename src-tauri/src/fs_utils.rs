@@ -0,0 +1,280 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Builds the sibling temp path used while atomically replacing `path`.
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+
+    path.with_file_name(format!(".{}.tmp-{}", file_name, uuid::Uuid::new_v4()))
+}
+
+/// App-specific folder inside the platform temp directory.
+pub fn app_temp_dir() -> PathBuf {
+    std::env::temp_dir().join("beaki")
+}
+
+/// Whether `name` is a temp file this app creates next to other files: an
+/// atomic-write temp (`.<name>.tmp-<uuid>`) or an I/O benchmark file.
+pub fn is_app_temp_file(name: &str) -> bool {
+    if name.starts_with(".beaki-benchmark-") {
+        return true;
+    }
+    name.starts_with('.')
+        && name
+            .rsplit_once(".tmp-")
+            .is_some_and(|(_, id)| uuid::Uuid::parse_str(id).is_ok())
+}
+
+/// Writes `contents` to a temp file next to `path`, syncs it, then renames it
+/// over the destination. The original file is untouched if anything fails
+/// before the rename, and the temp file is removed on failure. A symlinked
+/// `path` is written through to its target, and an existing file keeps its
+/// permissions.
+pub async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_atomic_from(path, contents).await
+}
+
+/// `write_atomic` with the contents copied from a reader; a read error
+/// fails the write like a write error does.
+pub async fn write_atomic_from<R: AsyncRead + Unpin>(path: &Path, mut contents: R) -> std::io::Result<()> {
+    // Renaming over a symlink would replace the link itself
+    let path = match tokio::fs::canonicalize(path).await {
+        Ok(target) => target,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e),
+    };
+    let permissions = tokio::fs::metadata(&path).await.ok().map(|m| m.permissions());
+    let temp_path = temp_path_for(&path);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        tokio::io::copy(&mut contents, &mut file).await?;
+        file.flush().await?;
+        file.sync_all().await?;
+        drop(file);
+        if let Some(permissions) = permissions {
+            tokio::fs::set_permissions(&temp_path, permissions).await?;
+        }
+        tokio::fs::rename(&temp_path, &path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+
+    result
+}
+
+/// Returns the last `n` lines of a file, reading backwards in chunks so large
+/// files aren't loaded whole.
+pub async fn tail_lines(path: &Path, n: usize) -> std::io::Result<Vec<String>> {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+
+    let mut position = len;
+    let mut tail: Vec<u8> = Vec::new();
+
+    // Stop once we have one more newline than requested lines, so the first
+    // line kept is complete.
+    while position > 0 && tail.iter().filter(|b| **b == b'\n').count() <= n {
+        let chunk_len = CHUNK_SIZE.min(position);
+        position -= chunk_len;
+
+        let mut chunk = vec![0u8; chunk_len as usize];
+        file.seek(SeekFrom::Start(position)).await?;
+        file.read_exact(&mut chunk).await?;
+
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&tail);
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.len().saturating_sub(n);
+
+    Ok(lines[start..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Counts newline-terminated lines (plus a trailing unterminated one) without
+/// holding the whole file in memory.
+pub async fn count_lines(path: &Path) -> std::io::Result<u64> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut count = 0u64;
+    let mut last_byte = None;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        count += buffer[..read].iter().filter(|b| **b == b'\n').count() as u64;
+        last_byte = Some(buffer[read - 1]);
+    }
+
+    if matches!(last_byte, Some(b) if b != b'\n') {
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Identity of a directory on disk, used to notice when a symlink leads back
+/// into a directory that's already being walked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DirKey {
+    #[cfg(unix)]
+    Inode { dev: u64, ino: u64 },
+    #[cfg(not(unix))]
+    Canonical(PathBuf),
+}
+
+pub fn dir_key(path: &Path) -> std::io::Result<DirKey> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path)?;
+        Ok(DirKey::Inode {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(DirKey::Canonical(std::fs::canonicalize(path)?))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WalkOutcome {
+    pub files: Vec<PathBuf>,
+    /// Directories not descended into because they were already visited,
+    /// either through a symlink cycle or a second link to the same directory.
+    pub skipped: Vec<PathBuf>,
+    /// Whether any skipped directory was an ancestor of its link (a true cycle).
+    pub has_cycle: bool,
+}
+
+/// Recursively collects files under `root`, following symlinks but never
+/// entering a directory twice. `exclude` is checked against every entry
+/// before it is visited. Runs synchronously; call from `spawn_blocking`.
+pub fn walk_files(root: &Path, exclude: &dyn Fn(&Path) -> bool) -> std::io::Result<WalkOutcome> {
+    let mut outcome = WalkOutcome::default();
+    let mut visited = std::collections::HashSet::new();
+    let mut ancestors = Vec::new();
+
+    walk_dir(root, exclude, &mut visited, &mut ancestors, &mut outcome)?;
+
+    Ok(outcome)
+}
+
+fn walk_dir(
+    dir: &Path,
+    exclude: &dyn Fn(&Path) -> bool,
+    visited: &mut std::collections::HashSet<DirKey>,
+    ancestors: &mut Vec<DirKey>,
+    outcome: &mut WalkOutcome,
+) -> std::io::Result<()> {
+    let key = dir_key(dir)?;
+
+    if ancestors.contains(&key) {
+        outcome.has_cycle = true;
+        outcome.skipped.push(dir.to_path_buf());
+        return Ok(());
+    }
+    if !visited.insert(key.clone()) {
+        outcome.skipped.push(dir.to_path_buf());
+        return Ok(());
+    }
+
+    ancestors.push(key);
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if exclude(&path) {
+            continue;
+        }
+
+        // `metadata` follows symlinks; broken links are skipped
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {
+                // Unreadable subdirectories shouldn't abort the whole walk
+                let _ = walk_dir(&path, exclude, visited, ancestors, outcome);
+            }
+            Ok(_) => outcome.files.push(path),
+            Err(_) => {}
+        }
+    }
+
+    ancestors.pop();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    /// Yields some data, then fails like a disk filling up mid-write.
+    struct FailingReader {
+        sent: bool,
+    }
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            if self.sent {
+                return Poll::Ready(Err(std::io::Error::other("disk full")));
+            }
+            self.sent = true;
+            buf.put_slice(b"{\"partial\":");
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn failed_atomic_write_keeps_original() {
+        let dir = std::env::temp_dir().join(format!("beaki-atomic-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("design.beaki");
+        std::fs::write(&path, "original").unwrap();
+
+        let result = tauri::async_runtime::block_on(write_atomic_from(&path, FailingReader { sent: false }));
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1, "temp file left behind");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_keeps_symlink_and_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("beaki-atomic-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("run.sh");
+        let link = dir.join("link.sh");
+        std::fs::write(&target, "old").unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        tauri::async_runtime::block_on(write_atomic(&link, b"new")).unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// How long a repository's status is served from the cache. Long enough to
+/// absorb a file browser re-rendering, short enough to feel live.
+const STATUS_CACHE_TTL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileState {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Untracked,
+    Ignored,
+    /// Unmerged: both sides changed the file during a merge or rebase.
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitFileStatus {
+    /// Absolute path. Untracked and ignored directories are reported once,
+    /// not file by file.
+    pub path: String,
+    pub status: GitFileState,
+    /// Whether the change is in the index (`git add`ed).
+    pub staged: bool,
+    /// Previous path of a renamed or copied file.
+    pub original_path: Option<String>,
+}
+
+pub type GitStatusCache = Arc<Mutex<HashMap<String, (Instant, Vec<GitFileStatus>)>>>;
+
+pub fn create_git_status_cache() -> GitStatusCache {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+async fn run_git(repo_root: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("not a git repository") {
+            return Err(format!("'{}' is not a git repository", repo_root));
+        }
+        return Err(format!("git {} failed: {}", args.join(" "), stderr.trim()));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Classifies a porcelain `XY` code: `X` is the index, `Y` the worktree.
+fn classify(x: u8, y: u8) -> GitFileState {
+    match (x, y) {
+        (b'?', b'?') => GitFileState::Untracked,
+        (b'!', b'!') => GitFileState::Ignored,
+        (b'D', b'D') | (b'A', b'A') | (b'U', _) | (_, b'U') => GitFileState::Conflicted,
+        (b'R', _) => GitFileState::Renamed,
+        (b'C', _) => GitFileState::Copied,
+        (b'A', _) => GitFileState::Added,
+        (b'D', _) | (_, b'D') => GitFileState::Deleted,
+        (b'T', _) | (_, b'T') => GitFileState::TypeChanged,
+        _ => GitFileState::Modified,
+    }
+}
+
+/// Parses `git status --porcelain=v1 -z` output. Entries are `XY path`,
+/// with renames and copies followed by a second entry holding the old path.
+fn parse_porcelain(output: &[u8], top_level: &Path) -> Vec<GitFileStatus> {
+    let mut entries = output.split(|b| *b == 0).filter(|e| !e.is_empty());
+    let mut statuses = Vec::new();
+    let absolute = |path: &[u8]| {
+        top_level
+            .join(String::from_utf8_lossy(path).as_ref())
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (x, y) = (entry[0], entry[1]);
+        let status = classify(x, y);
+        let original_path = if matches!(x, b'R' | b'C') {
+            entries.next().map(absolute)
+        } else {
+            None
+        };
+
+        statuses.push(GitFileStatus {
+            path: absolute(&entry[3..]),
+            status,
+            staged: !matches!(x, b' ' | b'?' | b'!') && status != GitFileState::Conflicted,
+            original_path,
+        });
+    }
+
+    statuses
+}
+
+/// Git status of every changed, untracked and ignored path in the
+/// repository containing `repo_root`, for badges in the file browser.
+/// Results are cached per root for two seconds.
+#[tauri::command]
+pub async fn git_status(
+    repo_root: String,
+    cache: tauri::State<'_, GitStatusCache>,
+) -> Result<Vec<GitFileStatus>, String> {
+    if let Some((fetched_at, statuses)) = cache.lock().await.get(&repo_root) {
+        if fetched_at.elapsed() < STATUS_CACHE_TTL {
+            return Ok(statuses.clone());
+        }
+    }
+
+    let top_level = run_git(&repo_root, &["rev-parse", "--show-toplevel"]).await?;
+    let top_level = PathBuf::from(String::from_utf8_lossy(&top_level).trim());
+
+    let output = run_git(&repo_root, &["status", "--porcelain=v1", "-z", "--ignored"]).await?;
+    let statuses = parse_porcelain(&output, &top_level);
+
+    let mut cache = cache.lock().await;
+    cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < STATUS_CACHE_TTL);
+    cache.insert(repo_root, (Instant::now(), statuses.clone()));
+    Ok(statuses)
+}
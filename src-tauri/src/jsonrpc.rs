@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::process_manager::ProcessMap;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum McpMessageError {
+    InvalidJson { message: String },
+    /// The message (or a batch entry) isn't a JSON object.
+    NotAnObject,
+    /// `jsonrpc` is missing or isn't `"2.0"`.
+    WrongVersion { found: Value },
+    /// `method` isn't a non-empty string.
+    InvalidMethod { found: Value },
+    /// `id` isn't a string, number or null.
+    InvalidId { found: Value },
+    /// A message without `method` must be a response with an `id` and
+    /// exactly one of `result` and `error`.
+    InvalidResponse { message: String },
+    EmptyBatch,
+    /// The stdio transport is newline-delimited, so a pretty-printed message
+    /// would arrive as several broken ones.
+    EmbeddedNewline,
+    /// Validation passed but writing to the process failed.
+    Send { message: String },
+}
+
+fn validate_object(message: &Value) -> Result<(), McpMessageError> {
+    let Value::Object(fields) = message else {
+        return Err(McpMessageError::NotAnObject);
+    };
+
+    match fields.get("jsonrpc") {
+        Some(Value::String(version)) if version == "2.0" => {}
+        other => {
+            return Err(McpMessageError::WrongVersion {
+                found: other.cloned().unwrap_or(Value::Null),
+            })
+        }
+    }
+
+    if let Some(id) = fields.get("id") {
+        if !matches!(id, Value::String(_) | Value::Number(_) | Value::Null) {
+            return Err(McpMessageError::InvalidId { found: id.clone() });
+        }
+    }
+
+    match fields.get("method") {
+        Some(Value::String(method)) if !method.is_empty() => Ok(()),
+        Some(other) => Err(McpMessageError::InvalidMethod {
+            found: other.clone(),
+        }),
+        None => {
+            if !fields.contains_key("id") {
+                return Err(McpMessageError::InvalidResponse {
+                    message: "a response needs an id".to_string(),
+                });
+            }
+            match (fields.contains_key("result"), fields.contains_key("error")) {
+                (true, false) | (false, true) => Ok(()),
+                _ => Err(McpMessageError::InvalidResponse {
+                    message: "a response needs exactly one of result and error".to_string(),
+                }),
+            }
+        }
+    }
+}
+
+/// Checks that `message` is a well-formed JSON-RPC 2.0 request,
+/// notification or response (or a batch of them).
+pub fn validate_message(message: &str) -> Result<(), McpMessageError> {
+    let value: Value = serde_json::from_str(message).map_err(|e| McpMessageError::InvalidJson {
+        message: e.to_string(),
+    })?;
+    if message.trim_end().contains(['\n', '\r']) {
+        return Err(McpMessageError::EmbeddedNewline);
+    }
+
+    match &value {
+        Value::Array(batch) if batch.is_empty() => Err(McpMessageError::EmptyBatch),
+        Value::Array(batch) => batch.iter().try_for_each(validate_object),
+        _ => validate_object(&value),
+    }
+}
+
+/// Like `send_mcp_message`, but rejects malformed JSON-RPC with a typed
+/// error before anything reaches the server.
+#[tauri::command]
+pub async fn send_mcp_validated(
+    connection_id: String,
+    message: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), McpMessageError> {
+    validate_message(&message)?;
+
+    crate::process_manager::send_mcp_message(connection_id, message, state)
+        .await
+        .map_err(|message| McpMessageError::Send { message })
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::{is_secret_key, redact_args};
+use crate::process_manager::{reap_exited, resolve_local_bin, ProcessMap};
+
+/// One running process, as it would be relaunched.
+struct LaunchEntry {
+    connection_id: String,
+    process_type: String,
+    program: String,
+    args: Vec<String>,
+    /// Sorted so the script is stable between exports.
+    env: BTreeMap<String, String>,
+    arg0: Option<String>,
+}
+
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Makes a value safe to put after `#`: a line break would end the comment
+/// and run the rest as script.
+fn comment_text(value: &str) -> String {
+    value.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+/// Whether `key` can be set as a variable by both scripts. `.env` files
+/// allow dots, which neither sh nor `$env:` accept unquoted.
+fn is_variable_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// POSIX sh script starting every process in the background and waiting for
+/// them. Secret variables are taken from the caller's environment and the
+/// script refuses to run without them.
+fn render_sh(cwd: &Path, entries: &[LaunchEntry]) -> String {
+    let mut script = String::from("#!/bin/sh\n");
+    script.push_str(&format!("# Relaunches {} process(es) exported from Beaki.\n", entries.len()));
+    script.push_str(&format!("cd {} || exit 1\n", sh_quote(&cwd.to_string_lossy())));
+
+    for entry in entries {
+        script.push_str(&format!("\n# {} {}\n", comment_text(&entry.process_type), entry.connection_id));
+        if let Some(arg0) = &entry.arg0 {
+            script.push_str(&format!("# originally run with argv[0] = {}\n", comment_text(arg0)));
+        }
+        script.push_str("(\n");
+        for (key, value) in &entry.env {
+            if !is_variable_name(key) {
+                script.push_str(&format!("  # skipped {}: not a valid variable name\n", comment_text(key)));
+            } else if is_secret_key(key) {
+                script.push_str(&format!("  export {key}=\"${{{key}:?set {key} before running}}\"\n"));
+            } else {
+                script.push_str(&format!("  export {}={}\n", key, sh_quote(value)));
+            }
+        }
+        let command: Vec<String> = std::iter::once(&entry.program)
+            .chain(&entry.args)
+            .map(|part| sh_quote(part))
+            .collect();
+        script.push_str(&format!("  exec {}\n", command.join(" ")));
+        script.push_str(") &\n");
+    }
+
+    script.push_str("\nwait\n");
+    script
+}
+
+/// PowerShell script starting every process in its own window-less job.
+/// Secret variables must already be set in the session.
+fn render_powershell(cwd: &Path, entries: &[LaunchEntry]) -> String {
+    let mut script = format!("# Relaunches {} process(es) exported from Beaki.\n", entries.len());
+    script.push_str("$ErrorActionPreference = 'Stop'\n");
+    script.push_str(&format!(
+        "Set-Location -LiteralPath {}\n",
+        powershell_quote(&cwd.to_string_lossy())
+    ));
+
+    for entry in entries {
+        script.push_str(&format!("\n# {} {}\n", comment_text(&entry.process_type), entry.connection_id));
+        let mut env_lines = Vec::new();
+        for (key, value) in &entry.env {
+            if !is_variable_name(key) {
+                script.push_str(&format!("# skipped {}: not a valid variable name\n", comment_text(key)));
+            } else if is_secret_key(key) {
+                script.push_str(&format!(
+                    "if (-not $env:{key}) {{ throw 'Set {key} before running' }}\n"
+                ));
+            } else {
+                env_lines.push(format!("  $env:{} = {}\n", key, powershell_quote(value)));
+            }
+        }
+        let args: Vec<String> = entry.args.iter().map(|arg| powershell_quote(arg)).collect();
+        script.push_str(&format!(
+            "Start-Job -ScriptBlock {{\n  Set-Location -LiteralPath $using:PWD\n{}  & {} {}\n}} | Out-Null\n",
+            env_lines.concat(),
+            powershell_quote(&entry.program),
+            args.join(" ")
+        ));
+    }
+
+    script.push_str("\nGet-Job | Receive-Job -Wait\n");
+    script
+}
+
+/// Generates a script that relaunches every running process with the same
+/// command, arguments, working directory and `env_file` variables: POSIX sh
+/// on Unix, PowerShell on Windows. Secret-looking arguments are redacted and
+/// secret-looking variables become placeholders read from the caller's
+/// environment.
+#[tauri::command]
+pub async fn export_launch_script(state: tauri::State<'_, ProcessMap>) -> Result<String, String> {
+    reap_exited(state.inner()).await;
+
+    let mut entries: Vec<(std::time::Instant, LaunchEntry)> = state
+        .lock()
+        .await
+        .values()
+        .filter(|process| process.exit_code.is_none())
+        .map(|process| {
+            let program = process
+                .options
+                .project_root
+                .as_deref()
+                .and_then(|root| resolve_local_bin(&process.info.command, Path::new(root)))
+                .unwrap_or_else(|| PathBuf::from(&process.info.command));
+
+            let entry = LaunchEntry {
+                connection_id: process.info.connection_id.clone(),
+                process_type: process.info.process_type.clone(),
+                program: program.to_string_lossy().into_owned(),
+                args: redact_args(&process.info.args),
+                env: process.env.clone().into_iter().collect(),
+                arg0: process.options.arg0.clone(),
+            };
+            (process.started_at, entry)
+        })
+        .collect();
+
+    // Launch in the original order
+    entries.sort_by_key(|(started_at, _)| *started_at);
+    let entries: Vec<LaunchEntry> = entries.into_iter().map(|(_, entry)| entry).collect();
+
+    // Spawned processes inherit the app's working directory
+    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+
+    if cfg!(windows) {
+        Ok(render_powershell(&cwd, &entries))
+    } else {
+        Ok(render_sh(&cwd, &entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sh_script_keeps_untrusted_text_in_comments() {
+        let entry = LaunchEntry {
+            connection_id: "abc".to_string(),
+            process_type: "mcp\nrm -rf ~".to_string(),
+            program: "node".to_string(),
+            args: Vec::new(),
+            env: BTreeMap::from([
+                ("app.name".to_string(), "x".to_string()),
+                ("PORT".to_string(), "3000".to_string()),
+            ]),
+            arg0: Some("busybox\r\ntouch /tmp/owned".to_string()),
+        };
+        let script = render_sh(Path::new("/srv"), &[entry]);
+
+        assert!(script.lines().all(|line| !line.starts_with("rm") && !line.starts_with("touch")));
+        assert!(script.contains("# skipped app.name: not a valid variable name"));
+        assert!(!script.contains("export app.name"));
+        assert!(script.contains("export PORT='3000'"));
+    }
+}
@@ -1,78 +1,203 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod browser;
+mod cli_tools;
+mod config_bundle;
+mod design;
+mod diagnostics;
+mod env_file;
+mod file_lock;
+mod file_type;
+mod files;
+mod fs_utils;
+mod git;
+mod hashing;
+mod jsonrpc;
+mod launch_script;
+mod mcp_config;
+mod mcp_http;
 mod ollama;
+mod ollama_generate;
+mod ollama_pull;
+mod output_websocket;
+mod priority;
 mod process_manager;
-
+mod process_output;
+mod requests;
+mod resources;
+mod semantic_search;
+mod sessions;
+mod settings;
+mod snapshot;
+mod text;
+mod watcher;
+
+use futures_util::future::Either;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tauri::Manager;
-use std::sync::mpsc;
+use tokio::sync::oneshot;
+
+/// How long a dialog may stay open before we assume it never appeared.
+const DEFAULT_DIALOG_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+enum DialogError {
+  /// No dialog result arrived in time, typically because the dialog plugin
+  /// couldn't show one (headless or unsupported desktop environment). The
+  /// frontend should fall back to manual path entry.
+  DialogUnavailable { message: String },
+}
+
+fn file_path_to_string(path: tauri_plugin_dialog::FilePath) -> String {
+  match path {
+    tauri_plugin_dialog::FilePath::Path(p) => p.to_string_lossy().to_string(),
+    tauri_plugin_dialog::FilePath::Url(url) => url.to_string(),
+  }
+}
+
+/// The dialog command currently waiting for a result, so `cancel_dialog` can
+/// resolve it early.
+#[derive(Default)]
+struct DialogState {
+  next_id: AtomicU64,
+  pending: std::sync::Mutex<Option<(u64, oneshot::Sender<()>)>>,
+}
+
+impl DialogState {
+  fn lock_pending(&self) -> std::sync::MutexGuard<'_, Option<(u64, oneshot::Sender<()>)>> {
+    self.pending.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+async fn await_dialog(
+  rx: oneshot::Receiver<Option<tauri_plugin_dialog::FilePath>>,
+  timeout_ms: Option<u64>,
+  state: &DialogState,
+) -> Result<Option<String>, DialogError> {
+  let timeout_ms = timeout_ms.unwrap_or(DEFAULT_DIALOG_TIMEOUT_MS);
+
+  let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+  let (cancel_tx, cancel_rx) = oneshot::channel();
+  *state.lock_pending() = Some((id, cancel_tx));
+
+  let wait = async {
+    match futures_util::future::select(rx, cancel_rx).await {
+      Either::Left((path, _)) => path.ok().flatten(), // Channel closed means no result
+      Either::Right((Ok(()), _)) => None,
+      // A newer dialog took over the cancel slot; keep waiting for ours
+      Either::Right((Err(_), rx)) => rx.await.ok().flatten(),
+    }
+  };
+  let result = tokio::time::timeout(Duration::from_millis(timeout_ms), wait).await;
+
+  let mut pending = state.lock_pending();
+  if pending.as_ref().map(|(pending_id, _)| *pending_id) == Some(id) {
+    *pending = None;
+  }
+  drop(pending);
+
+  match result {
+    Ok(path) => Ok(path.map(file_path_to_string)),
+    Err(_) => Err(DialogError::DialogUnavailable {
+      message: format!("No dialog response within {}ms", timeout_ms),
+    }),
+  }
+}
+
+/// Resolves the pending dialog command with `None`, e.g. when the user aborts
+/// from the app UI. Native dialogs can't be closed from code on every
+/// platform, so the window may stay up, but its result is ignored. Returns
+/// `false` if no dialog was pending.
+#[tauri::command]
+async fn cancel_dialog(state: tauri::State<'_, DialogState>) -> Result<bool, String> {
+  match state.lock_pending().take() {
+    Some((_, cancel)) => Ok(cancel.send(()).is_ok()),
+    None => Ok(false),
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileFilter {
+  name: String,
+  /// Extensions without the leading dot, e.g. `["svg", "png"]`.
+  extensions: Vec<String>,
+}
+
+/// Applies the caller's filters, or the Beaki design filter when none are
+/// given.
+fn with_filters(
+  mut dialog: tauri_plugin_dialog::FileDialogBuilder<tauri::Wry>,
+  filters: Option<Vec<FileFilter>>,
+) -> tauri_plugin_dialog::FileDialogBuilder<tauri::Wry> {
+  match filters.filter(|filters| !filters.is_empty()) {
+    Some(filters) => {
+      for filter in filters {
+        let extensions: Vec<&str> = filter
+          .extensions
+          .iter()
+          .map(|e| e.trim_start_matches('.'))
+          .collect();
+        dialog = dialog.add_filter(filter.name, &extensions);
+      }
+      dialog
+    }
+    None => dialog.add_filter("Beaki Design Files", &["beaki", "json"]),
+  }
+}
 
 #[tauri::command]
 async fn open_file_dialog(
   app: tauri::AppHandle,
-) -> Result<Option<String>, String> {
+  filters: Option<Vec<FileFilter>>,
+  timeout_ms: Option<u64>,
+  state: tauri::State<'_, DialogState>,
+) -> Result<Option<String>, DialogError> {
   use tauri_plugin_dialog::DialogExt;
 
-  let (tx, rx) = mpsc::channel();
+  let (tx, rx) = oneshot::channel();
 
-  app.dialog()
-    .file()
-    .add_filter("Beaki Design Files", &["beaki", "json"])
+  with_filters(app.dialog().file(), filters)
     .pick_file(move |path| {
       let _ = tx.send(path);
     });
 
-  // Convert sync receiver to async
-  match tokio::task::spawn_blocking(move || rx.recv()).await {
-    Ok(Ok(Some(path))) => {
-      let path_str = match path {
-        tauri_plugin_dialog::FilePath::Path(p) => p.to_string_lossy().to_string(),
-        tauri_plugin_dialog::FilePath::Url(url) => url.to_string(),
-      };
-      Ok(Some(path_str))
-    },
-    Ok(Ok(None)) => Ok(None),
-    _ => Ok(None), // User cancelled or channel closed
-  }
+  await_dialog(rx, timeout_ms, &state).await
 }
 
 #[tauri::command]
 async fn save_file_dialog(
   app: tauri::AppHandle,
   default_path: Option<String>,
-) -> Result<Option<String>, String> {
+  filters: Option<Vec<FileFilter>>,
+  timeout_ms: Option<u64>,
+  state: tauri::State<'_, DialogState>,
+) -> Result<Option<String>, DialogError> {
   use tauri_plugin_dialog::DialogExt;
 
-  let (tx, rx) = mpsc::channel();
+  let (tx, rx) = oneshot::channel();
 
-  app.dialog()
-    .file()
+  with_filters(app.dialog().file(), filters)
     .set_file_name(default_path.as_deref().unwrap_or("untitled.beaki"))
-    .add_filter("Beaki Design Files", &["beaki", "json"])
     .save_file(move |path| {
       let _ = tx.send(path);
     });
 
-  match tokio::task::spawn_blocking(move || rx.recv()).await {
-    Ok(Ok(Some(path))) => {
-      let path_str = match path {
-        tauri_plugin_dialog::FilePath::Path(p) => p.to_string_lossy().to_string(),
-        tauri_plugin_dialog::FilePath::Url(url) => url.to_string(),
-      };
-      Ok(Some(path_str))
-    },
-    Ok(Ok(None)) => Ok(None),
-    _ => Ok(None), // User cancelled or channel closed
-  }
+  await_dialog(rx, timeout_ms, &state).await
 }
 
 #[tauri::command]
 async fn open_directory_dialog(
   app: tauri::AppHandle,
-) -> Result<Option<String>, String> {
+  timeout_ms: Option<u64>,
+  state: tauri::State<'_, DialogState>,
+) -> Result<Option<String>, DialogError> {
   use tauri_plugin_dialog::DialogExt;
 
-  let (tx, rx) = mpsc::channel();
+  let (tx, rx) = oneshot::channel();
 
   app.dialog()
     .file()
@@ -80,17 +205,7 @@ async fn open_directory_dialog(
       let _ = tx.send(path);
     });
 
-  match tokio::task::spawn_blocking(move || rx.recv()).await {
-    Ok(Ok(Some(path))) => {
-      let path_str = match path {
-        tauri_plugin_dialog::FilePath::Path(p) => p.to_string_lossy().to_string(),
-        tauri_plugin_dialog::FilePath::Url(url) => url.to_string(),
-      };
-      Ok(Some(path_str))
-    },
-    Ok(Ok(None)) => Ok(None),
-    _ => Ok(None), // User cancelled or channel closed
-  }
+  await_dialog(rx, timeout_ms, &state).await
 }
 
 #[tauri::command]
@@ -100,9 +215,18 @@ async fn read_file(path: String) -> Result<String, String> {
     .map_err(|e| e.to_string())
 }
 
+/// Saves atomically so a crash mid-write never leaves a truncated file. With
+/// `backup`, the previous contents are first copied to `<path>.bak`.
 #[tauri::command]
-async fn write_file(path: String, contents: String) -> Result<(), String> {
-  tokio::fs::write(path, contents)
+async fn write_file(path: String, contents: String, backup: Option<bool>) -> Result<(), String> {
+  if backup.unwrap_or(false) && tokio::fs::try_exists(&path).await.unwrap_or(false) {
+    let backup_path = format!("{}.bak", path);
+    tokio::fs::copy(&path, &backup_path)
+      .await
+      .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+  }
+
+  fs_utils::write_atomic(std::path::Path::new(&path), contents.as_bytes())
     .await
     .map_err(|e| e.to_string())
 }
@@ -160,18 +284,28 @@ async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
 async fn detect_cli_tools() -> Result<Vec<serde_json::Value>, String> {
   use std::process::Command;
 
-  let cli_tools = vec!["codex", "geminicli", "claudecode"];
+  // Built-in tools, then the ones added with `set_custom_cli_tools`
+  let mut cli_tools: Vec<(String, String)> = ["codex", "geminicli", "claudecode"]
+    .iter()
+    .map(|tool| (tool.to_string(), tool.to_string()))
+    .collect();
+  cli_tools.extend(
+    cli_tools::load_custom_tools()
+      .await?
+      .into_iter()
+      .map(|tool| (tool.name, tool.command)),
+  );
   let mut detected: Vec<serde_json::Value> = Vec::new();
 
-  for tool_name in cli_tools {
+  for (tool_name, command) in cli_tools {
     // Check if tool exists in PATH
     let which_output = if cfg!(target_os = "windows") {
       Command::new("where")
-        .arg(tool_name)
+        .arg(&command)
         .output()
     } else {
       Command::new("which")
-        .arg(tool_name)
+        .arg(&command)
         .output()
     };
 
@@ -184,7 +318,7 @@ async fn detect_cli_tools() -> Result<Vec<serde_json::Value>, String> {
         if !command_path.is_empty() {
           // Try to get version
           let mut version = "unknown".to_string();
-          let version_output = Command::new(tool_name)
+          let version_output = Command::new(&command)
             .arg("--version")
             .output();
 
@@ -215,24 +349,15 @@ async fn detect_cli_tools() -> Result<Vec<serde_json::Value>, String> {
 }
 
 #[tauri::command]
-async fn detect_mcp_servers() -> Result<Vec<serde_json::Value>, String> {
+async fn detect_mcp_servers(project_root: Option<String>) -> Result<Vec<serde_json::Value>, String> {
   use std::fs;
-  use std::path::PathBuf;
 
   let mut servers: Vec<serde_json::Value> = Vec::new();
 
-  // Get home directory
-  let home_dir = match dirs::home_dir() {
-    Some(path) => path,
-    None => return Ok(servers), // No home directory, return empty
-  };
-
-  // Common MCP server config locations
-  let config_paths = vec![
-    home_dir.join(".config").join("mcp").join("servers.json"),
-    home_dir.join(".mcp").join("servers.json"),
-    PathBuf::from(".").join(".mcp").join("servers.json"),
-  ];
+  // Other clients' configs first so our own configs win for a shared id
+  let config_paths = mcp_config::client_config_paths()
+    .into_iter()
+    .chain(mcp_config::config_paths());
 
   for config_path in config_paths {
     if config_path.exists() {
@@ -240,22 +365,34 @@ async fn detect_mcp_servers() -> Result<Vec<serde_json::Value>, String> {
         Ok(content) => {
           match serde_json::from_str::<serde_json::Value>(&content) {
             Ok(config) => {
-              // Parse MCP server configs
-              if let Some(config_obj) = config.as_object() {
+              // Parse MCP server configs, flat or wrapped in `mcpServers`
+              if let Some(config_obj) = mcp_config::server_entries(&config) {
                 for (server_id, server_config) in config_obj {
                   if let Some(server_obj) = server_config.as_object() {
-                    servers.push(serde_json::json!({
-                      "id": format!("mcp-{}", server_id),
+                    // A locally installed server runs from node_modules/.bin, not PATH
+                    let local_command = match (&project_root, server_obj.get("command").and_then(|c| c.as_str())) {
+                      (Some(root), Some(command)) => process_manager::resolve_local_bin(command, std::path::Path::new(root)),
+                      _ => None,
+                    };
+                    let id = format!("mcp-{}", server_id);
+                    let server = serde_json::json!({
+                      "id": id,
                       "name": format!("{} (MCP)", server_id),
                       "type": "mcp",
                       "status": "available",
                       "endpoint": server_obj.get("endpoint"),
                       "command": server_obj.get("command"),
                       "args": server_obj.get("args"),
+                      "local_command": local_command,
                       "metadata": {
-                        "version": server_obj.get("version")
+                        "version": server_obj.get("version"),
+                        "env": server_obj.get("env")
                       }
-                    }));
+                    });
+                    match servers.iter_mut().find(|s| s["id"] == id) {
+                      Some(existing) => *existing = server,
+                      None => servers.push(server),
+                    }
                   }
                 }
               }
@@ -272,21 +409,54 @@ async fn detect_mcp_servers() -> Result<Vec<serde_json::Value>, String> {
     }
   }
 
+  mcp_config::annotate_port_conflicts(&mut servers).await;
+
   Ok(servers)
 }
 
 fn main() {
   // Create process manager state
   let process_map = process_manager::create_process_map();
+  process_manager::start_reaper(process_map.clone());
+  tauri::async_runtime::spawn_blocking(|| files::cleanup_stale_temp_files(files::STALE_TEMP_AGE, &[]));
+  tauri::async_runtime::spawn_blocking(|| {
+    if let Some(dir) = file_lock::default_designs_dir().filter(|d| d.is_dir()) {
+      let _ = file_lock::remove_stale_locks(&dir);
+    }
+  });
+  let watcher_map = watcher::create_watcher_map();
+  let request_map = requests::create_request_map();
+  let ollama_request_map = requests::create_ollama_request_map();
+  let http_connection_map = mcp_http::create_http_connection_map();
+  let help_cache = cli_tools::create_help_cache();
+  let embedding_cache = semantic_search::create_embedding_cache();
+  let output_websocket_map = output_websocket::create_output_websocket_map();
+  let git_status_cache = git::create_git_status_cache();
+  let snapshot_cache = snapshot::create_snapshot_cache();
+  let line_index_cache = files::create_line_index_cache();
+  let ollama_model_watch_map = ollama_pull::create_ollama_model_watch_map();
 
   tauri::Builder::default()
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_fs::init())
     .manage(process_map)
+    .manage(watcher_map)
+    .manage(request_map)
+    .manage(ollama_request_map)
+    .manage(http_connection_map)
+    .manage(help_cache)
+    .manage(DialogState::default())
+    .manage(embedding_cache)
+    .manage(output_websocket_map)
+    .manage(git_status_cache)
+    .manage(snapshot_cache)
+    .manage(line_index_cache)
+    .manage(ollama_model_watch_map)
     .invoke_handler(tauri::generate_handler![
       open_file_dialog,
       save_file_dialog,
       open_directory_dialog,
+      cancel_dialog,
       read_file,
       write_file,
       read_directory,
@@ -295,16 +465,136 @@ fn main() {
       get_documents_directory,
       ensure_directory_exists,
       rename_file,
+      files::stream_directory,
+      files::has_symlink_cycle,
+      files::canonicalize_path,
+      files::make_executable,
+      files::get_temp_directory,
+      files::cleanup_temp_files,
+      files::open_file,
+      file_type::inspect_dropped_paths,
+      file_type::detect_file_type,
+      file_lock::lock_file,
+      file_lock::unlock_file,
+      file_lock::is_file_open_elsewhere,
+      file_lock::clean_stale_locks,
+      files::read_and_hash_file,
+      files::write_file_if_unchanged,
+      files::truncate_file,
+      files::tail_file,
+      files::read_file_lines,
+      files::glob_files,
+      files::read_file_context,
+      git::git_status,
+      snapshot::snapshot_directory,
+      snapshot::diff_snapshots,
+      files::validate_project_dir,
+      text::detect_line_endings,
+      text::normalize_line_endings,
+      text::detect_encoding,
+      text::validate_utf8,
+      text::chunk_file,
+      requests::cancel_request,
+      requests::cancel_all_ollama,
+      design::save_design_minimal,
+      design::format_design,
+      design::rename_with_validation,
+      design::design_content_id,
+      design::read_json_pointer,
+      design::write_json_pointer,
+      design::summarize_design,
+      design::recover_design,
+      design::diff_autosave,
+      design::export_binary,
+      design::import_binary,
+      browser::open_in_browser,
       detect_cli_tools,
+      cli_tools::get_tool_help,
+      cli_tools::detect_cli_tools_diff,
+      cli_tools::get_search_path,
+      cli_tools::get_custom_cli_tools,
+      cli_tools::set_custom_cli_tools,
       detect_mcp_servers,
+      mcp_config::get_effective_mcp_config,
+      mcp_config::add_mcp_server,
+      mcp_config::remove_mcp_server,
+      mcp_config::parse_command_line,
+      mcp_config::validate_mcp_config,
+      mcp_config::repair_mcp_config,
+      settings::read_settings,
+      settings::write_settings,
+      config_bundle::export_config,
+      config_bundle::import_config,
+      diagnostics::export_integration_report,
+      diagnostics::check_permissions,
+      diagnostics::check_port,
+      diagnostics::benchmark_io,
+      resources::check_system_resources,
+      resources::total_process_resources,
+      watcher::watch_paths,
+      watcher::unwatch,
+      watcher::list_watchers,
+      watcher::unwatch_all,
+      watcher::wait_for_file,
       ollama::detect_ollama,
+      ollama::diagnose_ollama_connection,
+      ollama::ollama_log_info,
+      ollama::models_with_min_context,
+      ollama::ollama_models_grouped,
+      ollama::ollama_embeddings,
+      ollama_generate::ollama_generate,
+      ollama_generate::ollama_chat,
+      ollama_pull::ollama_pull,
+      ollama_pull::watch_ollama_models,
+      ollama_pull::unwatch_ollama_models,
+      semantic_search::semantic_file_search,
+      ollama::read_ollama_log_tail,
       process_manager::spawn_mcp_server,
+      env_file::read_env_file,
       process_manager::spawn_cli_agent,
+      process_manager::spawn_and_run,
+      process_manager::spawn_and_run_streamed,
       process_manager::send_mcp_message,
+      process_manager::broadcast_stdin,
+      jsonrpc::send_mcp_validated,
       process_manager::read_mcp_response,
+      process_manager::wait_for_mcp_message,
+      process_manager::probe_process_protocol,
+      process_manager::ping_mcp_server,
+      process_manager::measure_mcp_latency,
+      process_manager::call_mcp_tool,
+      process_manager::list_mcp_tools,
+      process_manager::list_mcp_resources,
+      process_manager::read_mcp_resource,
+      process_manager::subscribe_mcp_resource,
+      process_manager::unsubscribe_mcp_resource,
+      process_manager::respond_mcp_sampling,
+      process_manager::reset_idle_timer,
+      process_manager::reset_process_counters,
+      priority::set_process_priority,
       process_manager::kill_process,
+      process_manager::kill_processes_by_type,
+      process_manager::reap_zombies,
+      process_manager::prune_dead_processes,
       process_manager::list_processes,
       process_manager::get_process_info,
+      process_manager::wait_until_ready,
+      process_manager::dump_process_state,
+      process_manager::has_pending_output,
+      launch_script::export_launch_script,
+      sessions::list_sessions,
+      sessions::read_session,
+      sessions::diff_transcripts,
+      process_manager::get_all_mcp_capabilities,
+      mcp_http::connect_mcp_http,
+      mcp_http::send_mcp_http_message,
+      mcp_http::disconnect_mcp_http,
+      process_output::stream_process_output,
+      process_output::pause_process_output,
+      process_output::resume_process_output,
+      process_output::set_output_filter,
+      output_websocket::start_output_websocket,
+      output_websocket::stop_output_websocket,
     ])
     .setup(|app| {
       let window = app.get_webview_window("main").unwrap();
@@ -317,6 +607,14 @@ fn main() {
 
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while running tauri application")
+    .run(|app, event| {
+      if let tauri::RunEvent::Exit = event {
+        // Drop in-flight Ollama streams so generation doesn't carry on
+        // after the app has gone
+        let ollama_requests = app.state::<requests::OllamaRequestMap>().inner().clone();
+        tauri::async_runtime::block_on(requests::cancel_ollama_requests(&ollama_requests));
+      }
+    });
 }
@@ -0,0 +1,553 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// MCP config files in precedence order, lowest first: user-wide configs are
+/// overridden by the project-local one.
+pub fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Some(home_dir) = dirs::home_dir() {
+        paths.push(home_dir.join(".config").join("mcp").join("servers.json"));
+        paths.push(home_dir.join(".mcp").join("servers.json"));
+    }
+    paths.push(PathBuf::from(".").join(".mcp").join("servers.json"));
+
+    paths
+}
+
+/// Configs written by other MCP clients (Claude Desktop, Cursor), which use
+/// the `mcpServers`-wrapped layout. Only read for server detection.
+pub fn client_config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    // ~/Library/Application Support on macOS, %APPDATA% on Windows
+    if let Some(config_dir) = dirs::config_dir() {
+        paths.push(config_dir.join("Claude").join("claude_desktop_config.json"));
+    }
+    if let Some(home_dir) = dirs::home_dir() {
+        paths.push(home_dir.join(".cursor").join("mcp.json"));
+    }
+
+    paths
+}
+
+/// Escapes a key for use as a JSON pointer segment (RFC 6901).
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Deep-merges `source` into `target`, recording which source set each leaf.
+/// Objects merge key by key; any other value replaces what was there.
+fn merge_with_provenance(
+    target: &mut Value,
+    source: &Value,
+    pointer: &str,
+    source_name: &str,
+    provenance: &mut BTreeMap<String, String>,
+) {
+    match (target, source) {
+        (Value::Object(target_map), Value::Object(source_map)) => {
+            for (key, source_value) in source_map {
+                let child_pointer = format!("{}/{}", pointer, pointer_segment(key));
+                let target_value = target_map.entry(key.clone()).or_insert(Value::Null);
+                merge_with_provenance(target_value, source_value, &child_pointer, source_name, provenance);
+            }
+        }
+        (target, source) => {
+            // Anything previously recorded beneath this pointer is gone now
+            let prefix = format!("{}/", pointer);
+            provenance.retain(|key, _| !key.starts_with(&prefix));
+
+            *target = source.clone();
+            if let Value::Object(map) = source {
+                for (key, value) in map {
+                    let child_pointer = format!("{}/{}", pointer, pointer_segment(key));
+                    let mut child = Value::Null;
+                    merge_with_provenance(&mut child, value, &child_pointer, source_name, provenance);
+                }
+                if map.is_empty() {
+                    provenance.insert(pointer.to_string(), source_name.to_string());
+                }
+            } else {
+                provenance.insert(pointer.to_string(), source_name.to_string());
+            }
+        }
+    }
+}
+
+/// Reads every MCP config source in precedence order and deep-merges them.
+/// Returns the merged config, the sources that were read, and a map from
+/// JSON pointer to the source file that supplied each value.
+#[tauri::command]
+pub async fn get_effective_mcp_config() -> Result<Value, String> {
+    let mut merged = Value::Object(Map::new());
+    let mut provenance = BTreeMap::new();
+    let mut sources = Vec::new();
+    let mut errors = Vec::new();
+
+    for path in config_paths() {
+        let source_name = path.to_string_lossy().to_string();
+
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                errors.push(format!("Failed to read {}: {}", source_name, e));
+                continue;
+            }
+        };
+
+        match serde_json::from_str::<Value>(&content) {
+            Ok(config) => {
+                merge_with_provenance(&mut merged, &config, "", &source_name, &mut provenance);
+                sources.push(source_name);
+            }
+            Err(e) => errors.push(format!("Failed to parse {}: {}", source_name, e)),
+        }
+    }
+
+    Ok(serde_json::json!({
+        "config": merged,
+        "sources": sources,
+        "provenance": provenance,
+        "errors": errors,
+    }))
+}
+
+pub fn validate_server_definition(definition: &Value) -> Result<(), String> {
+    let object = definition
+        .as_object()
+        .ok_or_else(|| "Server definition must be an object".to_string())?;
+
+    let has_command = match object.get("command") {
+        Some(Value::String(command)) if !command.trim().is_empty() => true,
+        Some(Value::String(_)) | None => false,
+        Some(_) => return Err("'command' must be a string".to_string()),
+    };
+    let has_endpoint = object.get("endpoint").map(|e| e.is_string()).unwrap_or(false);
+    if !has_command && !has_endpoint {
+        return Err("Server definition needs a 'command' (or an 'endpoint' for HTTP servers)".to_string());
+    }
+
+    if let Some(args) = object.get("args") {
+        let args = args
+            .as_array()
+            .ok_or_else(|| "'args' must be an array".to_string())?;
+        if !args.iter().all(|a| a.is_string()) {
+            return Err("'args' must only contain strings".to_string());
+        }
+    }
+
+    if let Some(env) = object.get("env") {
+        let env = env
+            .as_object()
+            .ok_or_else(|| "'env' must be an object".to_string())?;
+        if !env.values().all(|v| v.is_string()) {
+            return Err("'env' values must be strings".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a config file for editing. A missing file starts as an empty object.
+pub async fn read_config_for_edit(config_path: &str) -> Result<(Value, Option<String>), String> {
+    match tokio::fs::read_to_string(config_path).await {
+        Ok(text) => {
+            let config = serde_json::from_str::<Value>(&text)
+                .map_err(|e| format!("Failed to parse {}: {}", config_path, e))?;
+            if !config.is_object() {
+                return Err(format!("{} does not contain a JSON object", config_path));
+            }
+            Ok((config, Some(text)))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((Value::Object(Map::new()), None)),
+        Err(e) => Err(format!("Failed to read {}: {}", config_path, e)),
+    }
+}
+
+/// Returns the map holding server entries: the `mcpServers` object when the
+/// file uses the wrapped layout, otherwise the top-level object.
+pub fn servers_map(config: &mut Value) -> &mut Map<String, Value> {
+    let wrapped = config
+        .get("mcpServers")
+        .map(|servers| servers.is_object())
+        .unwrap_or(false);
+
+    let servers = if wrapped {
+        config.get_mut("mcpServers")
+    } else {
+        Some(config)
+    };
+
+    servers
+        .and_then(|v| v.as_object_mut())
+        .expect("config root checked to be an object")
+}
+
+/// Read-only counterpart of `servers_map`. `None` when the root isn't an object.
+pub fn server_entries(config: &Value) -> Option<&Map<String, Value>> {
+    match config.get("mcpServers") {
+        Some(Value::Object(servers)) => Some(servers),
+        _ => config.as_object(),
+    }
+}
+
+pub async fn write_config(config_path: &str, config: &Value, original: Option<&str>) -> Result<(), String> {
+    let indent = original
+        .map(crate::design::detect_indent)
+        .unwrap_or_else(|| "  ".to_string());
+    let mut output = crate::design::to_pretty_json(config, &indent)?;
+    output.push('\n');
+
+    let path = std::path::Path::new(config_path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    crate::fs_utils::write_atomic(path, output.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", config_path, e))
+}
+
+/// Inserts or replaces a server entry in an MCP config file, keeping the
+/// file's layout (flat or `mcpServers`-wrapped), key order and indentation.
+#[tauri::command]
+pub async fn add_mcp_server(
+    config_path: String,
+    server_id: String,
+    definition: Value,
+) -> Result<(), String> {
+    if server_id.trim().is_empty() {
+        return Err("Server id must not be empty".to_string());
+    }
+    validate_server_definition(&definition)?;
+
+    let (mut config, original) = read_config_for_edit(&config_path).await?;
+    servers_map(&mut config).insert(server_id, definition);
+
+    write_config(&config_path, &config, original.as_deref()).await
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParsedCommand {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+/// Splits a pasted command line such as `npx -y @scope/server "/my path"`
+/// into the program and its args, with POSIX shell quoting and escaping,
+/// ready for `add_mcp_server` or `spawn_mcp_server`.
+#[tauri::command]
+pub async fn parse_command_line(cmd: String) -> Result<ParsedCommand, String> {
+    let mut words = shell_words::split(cmd.trim())
+        .map_err(|e| format!("Could not parse command line: {}", e))?
+        .into_iter();
+    let command = words
+        .next()
+        .ok_or_else(|| "Command line is empty".to_string())?;
+
+    Ok(ParsedCommand {
+        command,
+        args: words.collect(),
+    })
+}
+
+/// Removes a server entry from an MCP config file. Returns `false` if the
+/// entry wasn't there.
+#[tauri::command]
+pub async fn remove_mcp_server(config_path: String, server_id: String) -> Result<bool, String> {
+    let (mut config, original) = read_config_for_edit(&config_path).await?;
+    if original.is_none() {
+        return Ok(false);
+    }
+
+    if servers_map(&mut config).shift_remove(&server_id).is_none() {
+        return Ok(false);
+    }
+
+    write_config(&config_path, &config, original.as_deref()).await?;
+    Ok(true)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigIssue {
+    /// 1-based position, when the issue has one.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpConfigValidation {
+    pub valid: bool,
+    pub errors: Vec<ConfigIssue>,
+    pub suggestions: Vec<String>,
+}
+
+/// JSONC with comments and trailing commas blanked out. Blanking (rather than
+/// removing) keeps every remaining byte at its original line and column.
+struct StrippedJsonc {
+    json: String,
+    comments: Vec<usize>,
+    trailing_commas: Vec<usize>,
+}
+
+fn strip_jsonc(text: &str) -> StrippedJsonc {
+    let mut bytes = text.as_bytes().to_vec();
+    let mut comments = Vec::new();
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            b'/' if !in_string && matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')) => {
+                let block = bytes[i + 1] == b'*';
+                let end = if block {
+                    text[i + 2..].find("*/").map_or(bytes.len(), |e| i + 2 + e + 2)
+                } else {
+                    text[i..].find('\n').map_or(bytes.len(), |e| i + e)
+                };
+                comments.push(i);
+                for byte in &mut bytes[i..end] {
+                    if *byte != b'\n' && *byte != b'\r' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    // Comments are gone, so only whitespace can sit between a trailing
+    // comma and the closing bracket
+    let mut trailing_commas = Vec::new();
+    in_string = false;
+    i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            b',' if !in_string => {
+                let next = bytes[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+                if matches!(next, Some(b'}') | Some(b']')) {
+                    trailing_commas.push(i);
+                    bytes[i] = b' ';
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    StrippedJsonc {
+        // Only whole characters were replaced with ASCII spaces
+        json: String::from_utf8(bytes).expect("stripping keeps UTF-8 valid"),
+        comments,
+        trailing_commas,
+    }
+}
+
+/// 1-based line and column of a byte offset, counted like serde_json does.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+fn issue_at(text: &str, offset: usize, message: &str) -> ConfigIssue {
+    let (line, column) = position(text, offset);
+    ConfigIssue {
+        line: Some(line),
+        column: Some(column),
+        message: message.to_string(),
+    }
+}
+
+/// Parses a config leniently, accepting comments and trailing commas.
+fn parse_jsonc(text: &str) -> Result<(Value, StrippedJsonc), ConfigIssue> {
+    let stripped = strip_jsonc(text);
+    match serde_json::from_str::<Value>(&stripped.json) {
+        Ok(config) => Ok((config, stripped)),
+        Err(e) => Err(ConfigIssue {
+            line: Some(e.line()),
+            column: Some(e.column()),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Checks an MCP config file the way detection reads it (strict JSON) and
+/// reports every comment and trailing comma that would make detection skip
+/// it, any remaining syntax error, and invalid server entries.
+#[tauri::command]
+pub async fn validate_mcp_config(path: String) -> Result<McpConfigValidation, String> {
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    let mut errors = Vec::new();
+    let mut suggestions = Vec::new();
+
+    match parse_jsonc(&text) {
+        Ok((mut config, stripped)) => {
+            errors.extend(
+                stripped
+                    .comments
+                    .iter()
+                    .map(|&i| issue_at(&text, i, "Comments are not allowed in JSON")),
+            );
+            errors.extend(
+                stripped
+                    .trailing_commas
+                    .iter()
+                    .map(|&i| issue_at(&text, i, "Trailing comma")),
+            );
+            if !errors.is_empty() {
+                suggestions.push("Run repair_mcp_config to remove comments and trailing commas".to_string());
+            }
+
+            if config.is_object() {
+                for (server_id, definition) in servers_map(&mut config).iter() {
+                    if let Err(message) = validate_server_definition(definition) {
+                        errors.push(ConfigIssue {
+                            line: None,
+                            column: None,
+                            message: format!("Server '{}': {}", server_id, message),
+                        });
+                    }
+                }
+            } else {
+                errors.push(ConfigIssue {
+                    line: None,
+                    column: None,
+                    message: "The config must be a JSON object".to_string(),
+                });
+                suggestions.push("Wrap server entries in an object, e.g. { \"mcpServers\": { ... } }".to_string());
+            }
+        }
+        Err(issue) => {
+            errors.push(issue);
+            suggestions.push("Fix the syntax error at the reported line and column; repair can't fix it".to_string());
+        }
+    }
+
+    Ok(McpConfigValidation {
+        valid: errors.is_empty(),
+        errors,
+        suggestions,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpConfigRepair {
+    /// `false` when the file was already strict JSON and was left untouched.
+    pub changed: bool,
+    pub backup_path: Option<String>,
+    pub comments_removed: usize,
+    pub trailing_commas_removed: usize,
+}
+
+/// Rewrites an MCP config file as strict JSON, dropping comments and
+/// trailing commas, keeping key order and indentation. The original is
+/// copied to `<path>.bak` first.
+#[tauri::command]
+pub async fn repair_mcp_config(path: String) -> Result<McpConfigRepair, String> {
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    let (config, stripped) = parse_jsonc(&text).map_err(|issue| {
+        format!(
+            "Can't repair {}: {} (line {}, column {})",
+            path,
+            issue.message,
+            issue.line.unwrap_or_default(),
+            issue.column.unwrap_or_default()
+        )
+    })?;
+
+    if stripped.comments.is_empty() && stripped.trailing_commas.is_empty() {
+        return Ok(McpConfigRepair {
+            changed: false,
+            backup_path: None,
+            comments_removed: 0,
+            trailing_commas_removed: 0,
+        });
+    }
+
+    let backup_path = format!("{}.bak", path);
+    tokio::fs::copy(&path, &backup_path)
+        .await
+        .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+
+    write_config(&path, &config, Some(&text)).await?;
+
+    Ok(McpConfigRepair {
+        changed: true,
+        backup_path: Some(backup_path),
+        comments_removed: stripped.comments.len(),
+        trailing_commas_removed: stripped.trailing_commas.len(),
+    })
+}
+
+fn local_endpoint_port(endpoint: &str) -> Option<u16> {
+    let url = reqwest::Url::parse(endpoint).ok()?;
+    match url.host_str()? {
+        "localhost" | "127.0.0.1" | "0.0.0.0" | "[::1]" => url.port_or_known_default(),
+        _ => None,
+    }
+}
+
+/// Adds a `warnings` list to detected HTTP servers on local ports when two
+/// entries share a port, or when a server the app would launch itself finds
+/// its port already taken by another process.
+pub async fn annotate_port_conflicts(servers: &mut [Value]) {
+    let ports: Vec<Option<u16>> = servers
+        .iter()
+        .map(|s| s.get("endpoint").and_then(|e| e.as_str()).and_then(local_endpoint_port))
+        .collect();
+
+    for (index, server) in servers.iter_mut().enumerate() {
+        let Some(port) = ports[index] else {
+            continue;
+        };
+
+        let mut warnings = Vec::new();
+
+        let shared = ports
+            .iter()
+            .enumerate()
+            .any(|(other, p)| other != index && *p == Some(port));
+        if shared {
+            warnings.push(format!("Port {} is configured for more than one MCP server", port));
+        }
+
+        let launches_itself = server.get("command").map(|c| c.is_string()).unwrap_or(false);
+        if launches_itself {
+            let status = crate::diagnostics::port_status(port).await;
+            if !status.available {
+                warnings.push(match status.process_name {
+                    Some(name) => format!("Port {} is already in use by {}", port, name),
+                    None => format!("Port {} is already in use", port),
+                });
+            }
+        }
+
+        if !warnings.is_empty() {
+            if let Some(object) = server.as_object_mut() {
+                object.insert("warnings".to_string(), Value::from(warnings));
+            }
+        }
+    }
+}
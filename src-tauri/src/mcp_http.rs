@@ -0,0 +1,325 @@
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+/// An MCP server reached over HTTP, using either the SSE transport (GET an
+/// event stream, POST to the endpoint it announces) or streamable HTTP (POST
+/// to the endpoint, responses as JSON or an event stream).
+pub struct HttpConnection {
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+    /// Where messages are POSTed; announced by the `endpoint` SSE event.
+    post_url: Arc<Mutex<String>>,
+    /// `Mcp-Session-Id` assigned by a streamable HTTP server.
+    session_id: Arc<Mutex<Option<String>>>,
+    listener: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+pub type HttpConnectionMap = Arc<Mutex<HashMap<String, HttpConnection>>>;
+
+pub fn create_http_connection_map() -> HttpConnectionMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Default)]
+struct SseEvent {
+    event: Option<String>,
+    data: String,
+}
+
+/// Byte offset just past the blank line that ends the first complete event,
+/// accepting `\n`, `\r\n` and `\r` line endings.
+fn event_end(buffer: &[u8]) -> Option<usize> {
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < buffer.len() {
+        let terminator = match buffer[i] {
+            b'\n' => 1,
+            b'\r' => match buffer.get(i + 1) {
+                Some(b'\n') => 2,
+                Some(_) => 1,
+                // Could be the first half of a `\r\n` split across chunks
+                None => return None,
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if i == line_start {
+            return Some(i + terminator);
+        }
+        i += terminator;
+        line_start = i;
+    }
+    None
+}
+
+/// Incremental parser for `text/event-stream` bodies. Raw bytes are
+/// buffered and only complete events are decoded, so a multi-byte character
+/// or a `\r\n` split across chunks survives intact.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = event_end(&self.buffer) {
+            let block: Vec<u8> = self.buffer.drain(..end).collect();
+            let block = String::from_utf8_lossy(&block);
+            let mut event = SseEvent::default();
+            let mut data_lines = Vec::new();
+
+            for line in block.split(['\r', '\n']) {
+                if let Some(value) = line.strip_prefix("event:") {
+                    event.event = Some(value.trim().to_string());
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data_lines.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+                }
+            }
+
+            if !data_lines.is_empty() {
+                event.data = data_lines.join("\n");
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn emit_message(app: &AppHandle, connection_id: &str, message: &str) {
+    let _ = app.emit(
+        "mcp-http-message",
+        serde_json::json!({ "connection_id": connection_id, "message": message }),
+    );
+}
+
+fn build_headers(headers: &HashMap<String, String>) -> Result<reqwest::header::HeaderMap, String> {
+    let mut map = reqwest::header::HeaderMap::new();
+    for (name, value) in headers {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name '{}': {}", name, e))?;
+        let value = reqwest::header::HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header '{}': {}", name, e))?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+/// Reads an event stream until it ends, emitting `message` events and
+/// recording the POST endpoint when the server announces one. `is_listener`
+/// marks the long-lived GET stream, whose end means the connection closed.
+async fn consume_event_stream(
+    app: AppHandle,
+    connection_id: String,
+    base_url: reqwest::Url,
+    post_url: Arc<Mutex<String>>,
+    response: reqwest::Response,
+    is_listener: bool,
+) {
+    let mut parser = SseParser::default();
+    let mut stream = response.bytes_stream();
+
+    while let Some(Ok(chunk)) = stream.next().await {
+        for event in parser.push(&chunk) {
+            match event.event.as_deref() {
+                Some("endpoint") => {
+                    if let Ok(url) = base_url.join(event.data.trim()) {
+                        *post_url.lock().await = url.to_string();
+                    }
+                }
+                Some("message") | None => emit_message(&app, &connection_id, &event.data),
+                Some(_) => {}
+            }
+        }
+    }
+
+    if is_listener {
+        let _ = app.emit(
+            "mcp-http-closed",
+            serde_json::json!({ "connection_id": connection_id }),
+        );
+    }
+}
+
+/// Opens a connection to an HTTP MCP server and returns a connection id.
+/// Incoming messages are emitted as `mcp-http-message` events; send with
+/// `send_mcp_http_message`.
+#[tauri::command]
+pub async fn connect_mcp_http(
+    endpoint: String,
+    headers: Option<HashMap<String, String>>,
+    app: AppHandle,
+    state: tauri::State<'_, HttpConnectionMap>,
+) -> Result<String, String> {
+    let base_url =
+        reqwest::Url::parse(&endpoint).map_err(|e| format!("Invalid endpoint '{}': {}", endpoint, e))?;
+    let headers = headers.unwrap_or_default();
+    let client = reqwest::Client::new();
+    let connection_id = uuid::Uuid::new_v4().to_string();
+
+    let post_url = Arc::new(Mutex::new(endpoint.clone()));
+
+    let response = client
+        .get(base_url.clone())
+        .headers(build_headers(&headers)?)
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", endpoint, e))?;
+
+    // Streamable HTTP servers may not offer a GET stream at all; messages
+    // then only arrive as responses to our POSTs.
+    let listener = if response.status().is_success() {
+        Some(tauri::async_runtime::spawn(consume_event_stream(
+            app,
+            connection_id.clone(),
+            base_url,
+            post_url.clone(),
+            response,
+            true,
+        )))
+    } else if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        None
+    } else {
+        return Err(format!("{} returned status {}", endpoint, response.status()));
+    };
+
+    state.lock().await.insert(
+        connection_id.clone(),
+        HttpConnection {
+            headers,
+            client,
+            post_url,
+            session_id: Arc::new(Mutex::new(None)),
+            listener,
+        },
+    );
+
+    Ok(connection_id)
+}
+
+/// POSTs a JSON-RPC message to an HTTP MCP server. Any response body (plain
+/// JSON or an event stream) is emitted as `mcp-http-message` events.
+#[tauri::command]
+pub async fn send_mcp_http_message(
+    connection_id: String,
+    message: String,
+    app: AppHandle,
+    state: tauri::State<'_, HttpConnectionMap>,
+) -> Result<(), String> {
+    let (client, headers, post_url, session_id) = {
+        let connections = state.lock().await;
+        let connection = connections
+            .get(&connection_id)
+            .ok_or_else(|| format!("HTTP connection with ID '{}' not found", connection_id))?;
+        (
+            connection.client.clone(),
+            build_headers(&connection.headers)?,
+            connection.post_url.clone(),
+            connection.session_id.clone(),
+        )
+    };
+
+    let url = post_url.lock().await.clone();
+    let mut request = client
+        .post(&url)
+        .headers(headers)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::ACCEPT, "application/json, text/event-stream")
+        .body(message);
+    if let Some(id) = session_id.lock().await.clone() {
+        request = request.header("Mcp-Session-Id", id);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send to {}: {}", url, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("{} returned status {}", url, response.status()));
+    }
+
+    if let Some(id) = response
+        .headers()
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+    {
+        *session_id.lock().await = Some(id.to_string());
+    }
+
+    let is_event_stream = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("text/event-stream"))
+        .unwrap_or(false);
+
+    if is_event_stream {
+        let base_url = reqwest::Url::parse(&url).map_err(|e| e.to_string())?;
+        tauri::async_runtime::spawn(consume_event_stream(
+            app,
+            connection_id,
+            base_url,
+            post_url,
+            response,
+            false,
+        ));
+    } else {
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if !body.trim().is_empty() {
+            emit_message(&app, &connection_id, &body);
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn disconnect_mcp_http(
+    connection_id: String,
+    state: tauri::State<'_, HttpConnectionMap>,
+) -> Result<(), String> {
+    let connection = state
+        .lock()
+        .await
+        .remove(&connection_id)
+        .ok_or_else(|| format!("HTTP connection with ID '{}' not found", connection_id))?;
+
+    if let Some(listener) = connection.listener {
+        listener.abort();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_parser_joins_events_split_across_chunks() {
+        let mut parser = SseParser::default();
+        let body = "event: message\r\ndata: caf\u{e9}\r\n\r\ndata: next\n\n".as_bytes();
+
+        // Split inside the two-byte \u{e9} and between \r and \n
+        let split_char = body.iter().position(|b| *b == 0xc3).unwrap() + 1;
+        let split_crlf = body.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 1;
+        let mut events = parser.push(&body[..split_char]);
+        events.extend(parser.push(&body[split_char..split_crlf]));
+        events.extend(parser.push(&body[split_crlf..]));
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.as_deref(), Some("message"));
+        assert_eq!(events[0].data, "caf\u{e9}");
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].data, "next");
+    }
+}
@@ -1,10 +1,80 @@
+use futures_util::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::fs_utils;
+
+/// Maximum number of `/api/show` requests in flight when prefetching details.
+const DETAILS_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaModel {
     pub name: String,
     pub modified_at: String,
     pub size: i64,
+    #[serde(default)]
+    pub details: Option<OllamaModelDetails>,
+    /// Raw `parameters` block from `/api/show`, only set when details were requested.
+    #[serde(default)]
+    pub parameters: Option<String>,
+    /// Maximum context window in tokens, only set when details were requested.
+    #[serde(default)]
+    pub context_length: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaShowResponse {
+    #[serde(default)]
+    parameters: Option<String>,
+    #[serde(default)]
+    details: Option<OllamaModelDetails>,
+    #[serde(default)]
+    model_info: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl OllamaShowResponse {
+    /// Works out the model's context window. `model_info` carries the trained
+    /// maximum under an architecture-prefixed key (`llama.context_length`,
+    /// `qwen2.context_length`, ...); failing that, a `num_ctx` line in the
+    /// Modelfile parameters gives the configured window.
+    fn context_length(&self) -> Option<u64> {
+        let from_info = self.model_info.as_ref().and_then(|info| {
+            info.iter()
+                .filter(|(key, _)| key.ends_with(".context_length") || key.as_str() == "context_length")
+                .find_map(|(_, value)| value_as_u64(value))
+        });
+
+        from_info.or_else(|| {
+            self.parameters.as_deref().and_then(|parameters| {
+                parameters.lines().find_map(|line| {
+                    let mut parts = line.split_whitespace();
+                    match (parts.next(), parts.next()) {
+                        (Some("num_ctx"), Some(value)) => value.trim_matches('"').parse().ok(),
+                        _ => None,
+                    }
+                })
+            })
+        })
+    }
+}
+
+fn value_as_u64(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().filter(|f| *f > 0.0).map(|f| f as u64))
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,19 +89,178 @@ pub struct OllamaDetectionResult {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct OllamaLogInfo {
+    pub path: String,
+    pub size: u64,
+    pub line_count: u64,
+    /// Last modification time in seconds since the Unix epoch.
+    pub modified: Option<u64>,
+}
+
+/// Resolves the Ollama base URL: an explicit host wins, then `OLLAMA_HOST`,
+/// then the host configured in settings.
+pub async fn resolve_host(host: Option<String>) -> String {
+    let host = match host.filter(|h| !h.trim().is_empty()) {
+        Some(host) => host,
+        None => match std::env::var("OLLAMA_HOST") {
+            Ok(env_host) if !env_host.trim().is_empty() => env_host,
+            _ => crate::settings::load_settings()
+                .await
+                .map(|s| s.default_host)
+                .unwrap_or_else(|_| crate::settings::DEFAULT_OLLAMA_HOST.to_string()),
+        },
+    };
+
+    normalize_host(&host)
+}
+
+/// Port Ollama listens on unless told otherwise.
+const DEFAULT_OLLAMA_PORT: u16 = 11434;
+
+/// Turns an `OLLAMA_HOST`-style value into a base URL the way Ollama's own
+/// client does: `http://` is assumed, a plain-http host without a port gets
+/// 11434, and the listen-everywhere `0.0.0.0` is reached via 127.0.0.1.
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('/');
+    let (scheme, rest) = host.split_once("://").unwrap_or(("http", host));
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+    let authority = match authority.strip_prefix("0.0.0.0") {
+        Some(port) if port.is_empty() || port.starts_with(':') => format!("127.0.0.1{}", port),
+        _ => authority.to_string(),
+    };
+    // The last `:` of a bracketed IPv6 address is followed by `]`, not digits
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()));
+
+    if scheme == "http" && !has_port {
+        format!("{}://{}:{}{}", scheme, authority, DEFAULT_OLLAMA_PORT, path)
+    } else {
+        format!("{}://{}{}", scheme, authority, path)
+    }
+}
+
+fn is_local_host(base_url: &str) -> bool {
+    reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|h| h.to_string()))
+        .map(|h| matches!(h.as_str(), "localhost" | "127.0.0.1" | "0.0.0.0" | "[::1]" | "::1"))
+        .unwrap_or(false)
+}
+
+/// Candidate server log locations, most specific first.
+fn ollama_log_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if cfg!(target_os = "windows") {
+        if let Some(local) = dirs::data_local_dir() {
+            candidates.push(local.join("Ollama").join("server.log"));
+        }
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".ollama").join("logs").join("server.log"));
+    }
+
+    if cfg!(target_os = "linux") {
+        candidates.push(PathBuf::from("/var/log/ollama.log"));
+        candidates.push(PathBuf::from("/var/log/ollama/server.log"));
+    }
+
+    candidates
+}
+
+fn find_ollama_log() -> Result<PathBuf, String> {
+    let candidates = ollama_log_candidates();
+
+    candidates
+        .iter()
+        .find(|p| p.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            let searched: Vec<String> = candidates.iter().map(|p| p.to_string_lossy().to_string()).collect();
+            let hint = if cfg!(target_os = "linux") {
+                " (when Ollama runs as a systemd service, use `journalctl -u ollama`)"
+            } else {
+                ""
+            };
+            format!("Could not locate the Ollama log; searched {}{}", searched.join(", "), hint)
+        })
+}
+
+#[tauri::command]
+pub async fn ollama_log_info(host: Option<String>) -> Result<OllamaLogInfo, String> {
+    let base_url = resolve_host(host).await;
+    if !is_local_host(&base_url) {
+        return Err(format!(
+            "Ollama at {} is not local; its logs live on that machine",
+            base_url
+        ));
+    }
+
+    let path = find_ollama_log()?;
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let line_count = fs_utils::count_lines(&path)
+        .await
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+    Ok(OllamaLogInfo {
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        line_count,
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+    })
+}
+
+#[tauri::command]
+pub async fn read_ollama_log_tail(n: usize) -> Result<Vec<String>, String> {
+    let path = find_ollama_log()?;
+    fs_utils::tail_lines(&path, n)
+        .await
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))
+}
+
+/// How long `detect_ollama` waits for `/api/tags` before reporting Ollama as
+/// unavailable, so an unreachable host doesn't leave the UI hanging.
+const DETECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Lists the models at `host`, or at `OLLAMA_HOST` / the configured host
+/// when it's absent. Never fails: problems are reported in `error`.
 #[tauri::command]
-pub async fn detect_ollama() -> Result<OllamaDetectionResult, String> {
-    let url = "http://localhost:11434/api/tags";
+pub async fn detect_ollama(
+    with_details: Option<bool>,
+    host: Option<String>,
+) -> Result<OllamaDetectionResult, String> {
+    let base_url = resolve_host(host).await;
+    let base_url = base_url.as_str();
+    let url = format!("{}/api/tags", base_url);
+    let client = reqwest::Client::new();
 
-    match reqwest::get(url).await {
+    match client.get(&url).timeout(DETECT_TIMEOUT).send().await {
         Ok(response) => {
             if response.status().is_success() {
                 match response.json::<OllamaTagsResponse>().await {
-                    Ok(tags) => Ok(OllamaDetectionResult {
-                        available: true,
-                        models: tags.models,
-                        error: None,
-                    }),
+                    Ok(tags) => {
+                        let models = if with_details.unwrap_or(false) {
+                            fetch_model_details(&client, base_url, tags.models).await
+                        } else {
+                            tags.models
+                        };
+
+                        Ok(OllamaDetectionResult {
+                            available: true,
+                            models,
+                            error: None,
+                        })
+                    }
                     Err(e) => Ok(OllamaDetectionResult {
                         available: false,
                         models: vec![],
@@ -56,3 +285,301 @@ pub async fn detect_ollama() -> Result<OllamaDetectionResult, String> {
         }
     }
 }
+
+/// How long each probe in `diagnose_ollama_connection` may take.
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum OllamaConnectionStatus {
+    Reachable {
+        base_url: String,
+        version: Option<String>,
+    },
+    /// The configured remote host refused the connection but the same port
+    /// answers on localhost: Ollama is listening on 127.0.0.1 only.
+    BoundToLocalhostOnly {
+        base_url: String,
+        local_url: String,
+        hint: String,
+    },
+    Unreachable {
+        base_url: String,
+        message: String,
+    },
+}
+
+/// Probes `/api/version`, returning the reported version.
+async fn probe_version(client: &reqwest::Client, base_url: &str) -> Result<Option<String>, String> {
+    let response = client
+        .get(format!("{}/api/version", base_url))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not available: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Ollama returned status: {}", response.status()));
+    }
+
+    let body = response.json::<serde_json::Value>().await.unwrap_or_default();
+    Ok(body["version"].as_str().map(String::from))
+}
+
+/// Checks whether Ollama answers at the configured host. When a remote host
+/// can't be reached but the same port answers on localhost, reports
+/// `BoundToLocalhostOnly` with the `OLLAMA_HOST` fix instead of a bare
+/// connection error.
+#[tauri::command]
+pub async fn diagnose_ollama_connection(host: Option<String>) -> Result<OllamaConnectionStatus, String> {
+    let base_url = resolve_host(host).await;
+    let client = reqwest::Client::new();
+
+    let message = match probe_version(&client, &base_url).await {
+        Ok(version) => return Ok(OllamaConnectionStatus::Reachable { base_url, version }),
+        Err(message) => message,
+    };
+
+    if !is_local_host(&base_url) {
+        if let Ok(mut local) = reqwest::Url::parse(&base_url) {
+            if local.set_host(Some("127.0.0.1")).is_ok() {
+                let local_url = local.as_str().trim_end_matches('/').to_string();
+                if probe_version(&client, &local_url).await.is_ok() {
+                    return Ok(OllamaConnectionStatus::BoundToLocalhostOnly {
+                        hint: format!(
+                            "Ollama is running but only listening on localhost, so {} can't reach it. \
+                             Restart it with OLLAMA_HOST=0.0.0.0 to accept connections from other machines.",
+                            base_url
+                        ),
+                        base_url,
+                        local_url,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(OllamaConnectionStatus::Unreachable { base_url, message })
+}
+
+/// Fetches `/api/show` for every model with bounded concurrency and merges the
+/// parameters and details into each entry. A model whose details can't be
+/// fetched is returned unchanged rather than failing the whole detection.
+async fn fetch_model_details(
+    client: &reqwest::Client,
+    base_url: &str,
+    models: Vec<OllamaModel>,
+) -> Vec<OllamaModel> {
+    let url = format!("{}/api/show", base_url);
+
+    stream::iter(models)
+        .map(|mut model| {
+            let request = client
+                .post(&url)
+                .json(&serde_json::json!({ "name": model.name }));
+
+            async move {
+                let show = match request.send().await {
+                    Ok(response) if response.status().is_success() => {
+                        response.json::<OllamaShowResponse>().await.ok()
+                    }
+                    _ => None,
+                };
+
+                if let Some(show) = show {
+                    model.context_length = show.context_length();
+                    model.parameters = show.parameters;
+                    if show.details.is_some() {
+                        model.details = show.details;
+                    }
+                }
+
+                model
+            }
+        })
+        .buffered(DETAILS_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// Fetches the model list from `base_url`, optionally with per-model details.
+pub async fn fetch_models(base_url: &str, with_details: bool) -> Result<Vec<OllamaModel>, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/api/tags", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not available: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Ollama returned status: {}", response.status()));
+    }
+
+    let tags = response
+        .json::<OllamaTagsResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+
+    if with_details {
+        Ok(fetch_model_details(&client, base_url, tags.models).await)
+    } else {
+        Ok(tags.models)
+    }
+}
+
+/// Returns the models whose context window is at least `min_ctx` tokens.
+/// Models whose context length can't be determined are left out.
+#[tauri::command]
+pub async fn models_with_min_context(
+    min_ctx: u64,
+    host: Option<String>,
+) -> Result<Vec<OllamaModel>, String> {
+    let base_url = resolve_host(host).await;
+    let models = fetch_models(&base_url, true).await?;
+
+    Ok(models
+        .into_iter()
+        .filter(|model| model.context_length.map(|ctx| ctx >= min_ctx).unwrap_or(false))
+        .collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelVariant {
+    /// Full model name to pass to other commands, e.g. `llama3:8b-q4_0`.
+    pub name: String,
+    /// Tag after the colon; `latest` when the name has none.
+    pub tag: String,
+    pub size: i64,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelFamily {
+    pub base_name: String,
+    pub variants: Vec<ModelVariant>,
+    pub total_size: i64,
+}
+
+/// Splits a model name into its base name and tag. Registry hosts and the
+/// implicit `library/` namespace are dropped so `llama3` and
+/// `registry.ollama.ai/library/llama3:8b` land in one family; other
+/// namespaces (`user/model`, `hf.co/user/repo`) are kept. A colon inside
+/// the host (a port) isn't mistaken for the tag separator.
+pub fn split_model_name(name: &str) -> (String, String) {
+    let last_segment_start = name.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (path, tag) = match name[last_segment_start..].rfind(':') {
+        Some(i) => (&name[..last_segment_start + i], &name[last_segment_start + i + 1..]),
+        None => (name, ""),
+    };
+
+    let path = path.strip_prefix("registry.ollama.ai/").unwrap_or(path);
+    let path = path.strip_prefix("library/").unwrap_or(path);
+
+    let tag = if tag.is_empty() { "latest" } else { tag };
+    (path.to_lowercase(), tag.to_string())
+}
+
+/// Groups installed models by base name (`llama3:8b`, `llama3:8b-q4_0` and
+/// `llama3:latest` all belong to `llama3`), for the model picker. Families
+/// and their variants are sorted by name.
+#[tauri::command]
+pub async fn ollama_models_grouped(host: Option<String>) -> Result<Vec<ModelFamily>, String> {
+    let base_url = resolve_host(host).await;
+    let models = fetch_models(&base_url, false).await?;
+
+    let mut families: std::collections::BTreeMap<String, Vec<ModelVariant>> = Default::default();
+    for model in models {
+        let (base_name, tag) = split_model_name(&model.name);
+        let details = model.details.unwrap_or_default();
+        families.entry(base_name).or_default().push(ModelVariant {
+            name: model.name,
+            tag,
+            size: model.size,
+            parameter_size: details.parameter_size,
+            quantization_level: details.quantization_level,
+        });
+    }
+
+    Ok(families
+        .into_iter()
+        .map(|(base_name, mut variants)| {
+            variants.sort_by(|a, b| a.tag.cmp(&b.tag));
+            ModelFamily {
+                total_size: variants.iter().map(|v| v.size).sum(),
+                base_name,
+                variants,
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embeds `inputs` with one `/api/embed` request, returning one vector per
+/// input in the same order.
+pub async fn embed(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let response = client
+        .post(format!("{}/api/embed", base_url))
+        .json(&serde_json::json!({ "model": model, "input": inputs }))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not available: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Ollama returned status: {}", response.status()));
+    }
+
+    let embedded = response
+        .json::<OllamaEmbedResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+
+    if embedded.embeddings.len() != inputs.len() {
+        return Err(format!(
+            "Ollama returned {} embeddings for {} inputs",
+            embedded.embeddings.len(),
+            inputs.len()
+        ));
+    }
+
+    Ok(embedded.embeddings)
+}
+
+#[tauri::command]
+pub async fn ollama_embeddings(
+    model: String,
+    inputs: Vec<String>,
+    host: Option<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    let base_url = resolve_host(host).await;
+    embed(&reqwest::Client::new(), &base_url, &model, &inputs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_host_fills_in_scheme_and_port() {
+        assert_eq!(normalize_host("localhost"), "http://localhost:11434");
+        assert_eq!(normalize_host("example.com:8080"), "http://example.com:8080");
+        assert_eq!(normalize_host("http://[::1]/"), "http://[::1]:11434");
+        assert_eq!(normalize_host("http://[::1]:9000"), "http://[::1]:9000");
+        assert_eq!(normalize_host("https://ollama.example.com/"), "https://ollama.example.com");
+    }
+
+    #[test]
+    fn normalize_host_connects_to_loopback_for_any_address() {
+        assert_eq!(normalize_host("0.0.0.0"), "http://127.0.0.1:11434");
+        assert_eq!(normalize_host("http://0.0.0.0:11500"), "http://127.0.0.1:11500");
+    }
+}
@@ -0,0 +1,297 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::ollama::resolve_host;
+use crate::requests::{self, CancelFlag, OllamaRequestMap, RequestMap};
+
+/// How long connecting to Ollama may take before a generation fails.
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Generation limits passed through to Ollama's `options`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GenerationOptions {
+    /// Maximum number of tokens to generate.
+    pub num_predict: Option<i64>,
+    /// Generation ends at the first of these; the stop text isn't returned.
+    #[serde(default)]
+    pub stop: Vec<String>,
+}
+
+impl GenerationOptions {
+    fn to_api_options(&self) -> Value {
+        let mut options = Map::new();
+        if let Some(num_predict) = self.num_predict {
+            options.insert("num_predict".to_string(), json!(num_predict));
+        }
+        if !self.stop.is_empty() {
+            options.insert("stop".to_string(), json!(self.stop));
+        }
+        Value::Object(options)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TokenPayload {
+    request_id: String,
+    text: String,
+}
+
+/// Accumulates generated text and cuts it at the first stop sequence. Ollama
+/// applies `stop` itself, but this guarantees the returned text never
+/// contains one even if the server lets it through, and lets us stop reading
+/// the stream as soon as one appears.
+struct StopScanner {
+    stops: Vec<String>,
+    text: String,
+    /// Bytes of `text` already handed out as tokens.
+    emitted: usize,
+    stopped: bool,
+}
+
+impl StopScanner {
+    fn new(stops: &[String]) -> Self {
+        Self {
+            stops: stops.iter().filter(|s| !s.is_empty()).cloned().collect(),
+            text: String::new(),
+            emitted: 0,
+            stopped: false,
+        }
+    }
+
+    /// Adds a chunk and returns the text that is now safe to emit: anything
+    /// that can't be the start of a stop sequence.
+    fn push(&mut self, chunk: &str) -> String {
+        self.text.push_str(chunk);
+
+        if let Some(position) = self.stops.iter().filter_map(|stop| self.text.find(stop.as_str())).min() {
+            self.text.truncate(position);
+            self.stopped = true;
+            return self.take_until(position);
+        }
+
+        // Hold back a tail that could still grow into a stop sequence
+        let longest = self.stops.iter().map(|s| s.len()).max().unwrap_or(0);
+        let mut safe = self.text.len().saturating_sub(longest.saturating_sub(1)).max(self.emitted);
+        while !self.text.is_char_boundary(safe) {
+            safe -= 1;
+        }
+        self.take_until(safe)
+    }
+
+    fn take_until(&mut self, end: usize) -> String {
+        let start = self.emitted.min(end);
+        self.emitted = end.max(self.emitted);
+        self.text[start..end].to_string()
+    }
+
+    /// Returns whatever was held back once the stream has ended.
+    fn finish(&mut self) -> String {
+        self.take_until(self.text.len())
+    }
+}
+
+fn emit_token(app: &AppHandle, request_id: Option<&str>, text: String) {
+    if let (Some(request_id), false) = (request_id, text.is_empty()) {
+        let _ = app.emit(
+            "ollama-token",
+            TokenPayload {
+                request_id: request_id.to_string(),
+                text,
+            },
+        );
+    }
+}
+
+/// An `/api/generate` or `/api/chat` request and how to read it: where the
+/// text sits in each response object and which stop sequences end it.
+struct Generation<'a> {
+    url: String,
+    body: Value,
+    text_of: fn(&Value) -> Option<&str>,
+    stops: &'a [String],
+}
+
+/// Sends a generate or chat request and returns the generated text. With a
+/// `request_id` the response is streamed, each piece emitted as an
+/// `ollama-token` event, and the request can be cancelled with
+/// `cancel_request`; otherwise a single non-streaming response is read.
+/// Cancelling a stream returns the text so far.
+async fn run_generation(
+    app: &AppHandle,
+    generation: Generation<'_>,
+    request_id: Option<&str>,
+    flag: &CancelFlag,
+) -> Result<String, String> {
+    let Generation {
+        url,
+        mut body,
+        text_of,
+        stops,
+    } = generation;
+    body["stream"] = json!(request_id.is_some());
+
+    // Only connecting is bounded; generating can legitimately take minutes
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not available: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("Ollama returned status {}: {}", status, detail.trim()));
+    }
+
+    let mut scanner = StopScanner::new(stops);
+
+    if request_id.is_none() {
+        let value: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+        scanner.push(text_of(&value).unwrap_or_default());
+        scanner.finish();
+        return Ok(scanner.text);
+    }
+
+    let mut stream = response.bytes_stream();
+    let mut pending = Vec::new();
+
+    'read: while let Some(Some(chunk)) = flag.run(stream.next()).await {
+        if flag.is_cancelled() {
+            break;
+        }
+        pending.extend_from_slice(&chunk.map_err(|e| format!("Ollama stream failed: {}", e))?);
+
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let Ok(value) = serde_json::from_slice::<Value>(&line) else {
+                continue;
+            };
+            if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+                return Err(format!("Ollama error: {}", error));
+            }
+
+            let token = scanner.push(text_of(&value).unwrap_or_default());
+            emit_token(app, request_id, token);
+
+            // Dropping the stream closes the connection, which ends generation
+            if scanner.stopped || value.get("done").and_then(|d| d.as_bool()) == Some(true) {
+                break 'read;
+            }
+        }
+    }
+
+    let rest = scanner.finish();
+    emit_token(app, request_id, rest);
+    Ok(scanner.text)
+}
+
+/// Runs a generation tracked for `cancel_all_ollama`, and for
+/// `cancel_request` when it has a `request_id`.
+async fn run_registered(
+    app: &AppHandle,
+    generation: Generation<'_>,
+    request_id: Option<String>,
+    requests_state: &RequestMap,
+    ollama_requests: &OllamaRequestMap,
+) -> Result<String, String> {
+    let flag = match &request_id {
+        Some(request_id) => requests::register(requests_state, request_id).await?,
+        None => CancelFlag::default(),
+    };
+    let key = requests::track_ollama(ollama_requests, &flag).await;
+
+    let result = match request_id.as_deref() {
+        Some(request_id) => run_generation(app, generation, Some(request_id), &flag).await,
+        // Nothing to return early without a stream; drop the request instead
+        None => flag
+            .run(run_generation(app, generation, None, &flag))
+            .await
+            .unwrap_or_else(|| Err("Ollama request was cancelled".to_string())),
+    };
+
+    requests::untrack_ollama(ollama_requests, &key).await;
+    if let Some(request_id) = &request_id {
+        requests::finish(requests_state, request_id).await;
+    }
+    result
+}
+
+/// Completes `prompt` with `/api/generate` and returns the generated text.
+#[tauri::command]
+pub async fn ollama_generate(
+    model: String,
+    prompt: String,
+    host: Option<String>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+    app: AppHandle,
+    requests_state: tauri::State<'_, RequestMap>,
+    ollama_requests: tauri::State<'_, OllamaRequestMap>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let url = format!("{}/api/generate", resolve_host(host).await);
+    let body = json!({ "model": model, "prompt": prompt, "options": options.to_api_options() });
+
+    let generation = Generation {
+        url,
+        body,
+        text_of: |value| value.get("response").and_then(|r| r.as_str()),
+        stops: &options.stop,
+    };
+    run_registered(
+        &app,
+        generation,
+        request_id,
+        &requests_state,
+        &ollama_requests,
+    )
+    .await
+}
+
+/// Continues a conversation with `/api/chat` and returns the assistant reply.
+#[tauri::command]
+pub async fn ollama_chat(
+    model: String,
+    messages: Vec<ChatMessage>,
+    host: Option<String>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+    app: AppHandle,
+    requests_state: tauri::State<'_, RequestMap>,
+    ollama_requests: tauri::State<'_, OllamaRequestMap>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let url = format!("{}/api/chat", resolve_host(host).await);
+    let body = json!({ "model": model, "messages": messages, "options": options.to_api_options() });
+
+    let generation = Generation {
+        url,
+        body,
+        text_of: |value| value.pointer("/message/content").and_then(|c| c.as_str()),
+        stops: &options.stop,
+    };
+    run_registered(
+        &app,
+        generation,
+        request_id,
+        &requests_state,
+        &ollama_requests,
+    )
+    .await
+}
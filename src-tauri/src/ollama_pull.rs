@@ -0,0 +1,217 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+use crate::ollama::{fetch_models, resolve_host, OllamaModel};
+use crate::requests::{self, CancelFlag, OllamaRequestMap, RequestMap};
+
+const DEFAULT_WATCH_INTERVAL_MS: u64 = 5000;
+/// Floor for the watch interval; listing models is cheap but not free.
+const MIN_WATCH_INTERVAL_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+struct PullProgress {
+    model: String,
+    request_id: Option<String>,
+    status: String,
+    digest: Option<String>,
+    total: Option<u64>,
+    completed: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelsUpdated<'a> {
+    base_url: &'a str,
+    /// Set for updates from `watch_ollama_models`.
+    watch_id: Option<&'a str>,
+    models: &'a [OllamaModel],
+}
+
+/// Running `watch_ollama_models` polls by watch id.
+pub type OllamaModelWatchMap = Arc<Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>>;
+
+pub fn create_ollama_model_watch_map() -> OllamaModelWatchMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// What identifies a model list for change detection: re-pulling a model
+/// changes its `modified_at` even when the name stays the same.
+fn fingerprint(models: &[OllamaModel]) -> Vec<(String, String, i64)> {
+    let mut fingerprint: Vec<_> = models
+        .iter()
+        .map(|m| (m.name.clone(), m.modified_at.clone(), m.size))
+        .collect();
+    fingerprint.sort();
+    fingerprint
+}
+
+async fn run_pull(
+    app: &AppHandle,
+    base_url: &str,
+    model: &str,
+    request_id: Option<&str>,
+    flag: &CancelFlag,
+) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/pull", base_url))
+        .json(&json!({ "model": model, "stream": true }))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not available: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("Ollama returned status {}: {}", status, detail.trim()));
+    }
+
+    let mut stream = response.bytes_stream();
+    let mut pending = Vec::new();
+
+    loop {
+        let chunk = match flag.run(stream.next()).await {
+            _ if flag.is_cancelled() => return Err(format!("Pull of '{}' was cancelled", model)),
+            Some(Some(chunk)) => chunk,
+            _ => break,
+        };
+        pending.extend_from_slice(&chunk.map_err(|e| format!("Ollama stream failed: {}", e))?);
+
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let Ok(value) = serde_json::from_slice::<Value>(&line) else {
+                continue;
+            };
+            if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+                return Err(format!("Ollama error: {}", error));
+            }
+
+            let status = value.get("status").and_then(|s| s.as_str()).unwrap_or_default();
+            let _ = app.emit(
+                "ollama-pull-progress",
+                PullProgress {
+                    model: model.to_string(),
+                    request_id: request_id.map(String::from),
+                    status: status.to_string(),
+                    digest: value.get("digest").and_then(|d| d.as_str()).map(String::from),
+                    total: value.get("total").and_then(|t| t.as_u64()),
+                    completed: value.get("completed").and_then(|c| c.as_u64()),
+                },
+            );
+
+            if status == "success" {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(format!("Ollama closed the pull of '{}' before it finished", model))
+}
+
+/// Downloads `model`, emitting `ollama-pull-progress` events as layers
+/// arrive. When the pull succeeds the refreshed model list is emitted as a
+/// `models-updated` event. With a `request_id` the pull can be stopped with
+/// `cancel_request`; `cancel_all_ollama` stops it either way.
+#[tauri::command]
+pub async fn ollama_pull(
+    model: String,
+    host: Option<String>,
+    request_id: Option<String>,
+    app: AppHandle,
+    requests_state: tauri::State<'_, RequestMap>,
+    ollama_requests: tauri::State<'_, OllamaRequestMap>,
+) -> Result<(), String> {
+    let base_url = resolve_host(host).await;
+
+    let flag = match &request_id {
+        Some(request_id) => requests::register(&requests_state, request_id).await?,
+        None => CancelFlag::default(),
+    };
+    let key = requests::track_ollama(&ollama_requests, &flag).await;
+    let result = run_pull(&app, &base_url, &model, request_id.as_deref(), &flag).await;
+    requests::untrack_ollama(&ollama_requests, &key).await;
+    if let Some(request_id) = &request_id {
+        requests::finish(&requests_state, request_id).await;
+    }
+    result?;
+
+    // The pull itself succeeded; a failed refresh only means no event
+    if let Ok(models) = fetch_models(&base_url, false).await {
+        let _ = app.emit(
+            "models-updated",
+            ModelsUpdated {
+                base_url: &base_url,
+                watch_id: None,
+                models: &models,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Polls the model list every `interval_ms` (default 5s) and emits it as a
+/// `models-updated` event when it differs from the last one, including once
+/// at the start. Polls where Ollama can't be reached are skipped. Returns a
+/// watch id for `unwatch_ollama_models`.
+#[tauri::command]
+pub async fn watch_ollama_models(
+    host: Option<String>,
+    interval_ms: Option<u64>,
+    app: AppHandle,
+    state: tauri::State<'_, OllamaModelWatchMap>,
+) -> Result<String, String> {
+    let base_url = resolve_host(host).await;
+    let interval = Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_WATCH_INTERVAL_MS)
+            .max(MIN_WATCH_INTERVAL_MS),
+    );
+    let watch_id = uuid::Uuid::new_v4().to_string();
+
+    let task = {
+        let watch_id = watch_id.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut last = None;
+            loop {
+                if let Ok(models) = fetch_models(&base_url, false).await {
+                    let current = fingerprint(&models);
+                    if last.as_ref() != Some(&current) {
+                        let _ = app.emit(
+                            "models-updated",
+                            ModelsUpdated {
+                                base_url: &base_url,
+                                watch_id: Some(&watch_id),
+                                models: &models,
+                            },
+                        );
+                        last = Some(current);
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    };
+
+    state.lock().await.insert(watch_id.clone(), task);
+    Ok(watch_id)
+}
+
+/// Stops a `watch_ollama_models` poll. Returns `false` for an unknown id.
+#[tauri::command]
+pub async fn unwatch_ollama_models(
+    watch_id: String,
+    state: tauri::State<'_, OllamaModelWatchMap>,
+) -> Result<bool, String> {
+    match state.lock().await.remove(&watch_id) {
+        Some(task) => {
+            task.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,181 @@
+use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Mutex};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::process_manager::ProcessMap;
+use crate::process_output::{output_stream, OutputStream};
+
+/// Lines buffered per client before a slow client starts missing lines.
+const CLIENT_BUFFER_LINES: usize = 1024;
+
+/// A WebSocket server mirroring one connection's output.
+pub struct OutputWebSocket {
+    pub url: String,
+    output: Arc<OutputStream>,
+    accept_task: tauri::async_runtime::JoinHandle<()>,
+}
+
+pub type OutputWebSocketMap = Arc<Mutex<HashMap<String, OutputWebSocket>>>;
+
+pub fn create_output_websocket_map() -> OutputWebSocketMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Sends every mirrored line to one client as a text message until the
+/// mirror is cleared or the client disconnects. Incoming messages are
+/// ignored apart from close frames. The handshake is refused unless the
+/// request path is the mirror's secret token, so a web page that guesses the
+/// port still can't read the output.
+async fn serve_client(stream: TcpStream, path: Arc<str>, mut lines: broadcast::Receiver<String>) {
+    let check_path = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if request.uri().path() == &*path {
+            return Ok(response);
+        }
+        let mut refused = ErrorResponse::new(None);
+        *refused.status_mut() = StatusCode::FORBIDDEN;
+        Err(refused)
+    };
+    let Ok(socket) = tokio_tungstenite::accept_hdr_async(stream, check_path).await else {
+        return;
+    };
+    let (mut sink, mut incoming) = socket.split();
+
+    loop {
+        let next_line = std::pin::pin!(lines.recv());
+        let next_incoming = std::pin::pin!(incoming.next());
+
+        match futures_util::future::select(next_line, next_incoming).await {
+            futures_util::future::Either::Left((Ok(line), _)) => {
+                let line = line.trim_end_matches(['\r', '\n']).to_string();
+                if sink.send(Message::text(line)).await.is_err() {
+                    return;
+                }
+            }
+            futures_util::future::Either::Left((Err(broadcast::error::RecvError::Lagged(skipped)), _)) => {
+                let notice = format!("[{} lines skipped]", skipped);
+                if sink.send(Message::text(notice)).await.is_err() {
+                    return;
+                }
+            }
+            futures_util::future::Either::Left((Err(broadcast::error::RecvError::Closed), _)) => {
+                let _ = sink.send(Message::Close(None)).await;
+                return;
+            }
+            futures_util::future::Either::Right((Some(Ok(message)), _)) => {
+                if message.is_close() {
+                    return;
+                }
+            }
+            futures_util::future::Either::Right((_, _)) => return,
+        }
+    }
+}
+
+/// Accepts clients until the process's output ends, then drops its map
+/// entry so the port is released and the mirror can be started again.
+async fn accept_clients(
+    listener: TcpListener,
+    output: Arc<OutputStream>,
+    path: Arc<str>,
+    sockets: OutputWebSocketMap,
+) {
+    // Only watched for the mirror closing, which happens when stdout ends
+    let mut ended = output.subscribe_mirror();
+
+    loop {
+        let accepted = std::pin::pin!(listener.accept());
+        let closed = std::pin::pin!(async {
+            if let Some(ended) = ended.as_mut() {
+                while !matches!(ended.recv().await, Err(broadcast::error::RecvError::Closed)) {}
+            }
+        });
+
+        match futures_util::future::select(accepted, closed).await {
+            futures_util::future::Either::Left((Ok((stream, _)), _)) => {
+                let Some(lines) = output.subscribe_mirror() else {
+                    break;
+                };
+                tauri::async_runtime::spawn(serve_client(stream, path.clone(), lines));
+            }
+            futures_util::future::Either::Left((Err(_), _)) | futures_util::future::Either::Right(_) => break,
+        }
+    }
+
+    let mut sockets = sockets.lock().await;
+    let ours = sockets
+        .iter()
+        .find(|(_, socket)| Arc::ptr_eq(&socket.output, &output))
+        .map(|(id, _)| id.clone());
+    if let Some(id) = ours {
+        sockets.remove(&id);
+    }
+}
+
+/// Mirrors a streaming connection's stdout lines to a WebSocket server, one
+/// text message per line, so other tools can watch an agent remotely.
+/// Listens on localhost unless `allow_remote` is set; `port` 0 picks a free
+/// port. Returns the `ws://` URL to connect to, whose path is a random token
+/// clients must use.
+#[tauri::command]
+pub async fn start_output_websocket(
+    connection_id: String,
+    port: u16,
+    allow_remote: Option<bool>,
+    state: tauri::State<'_, ProcessMap>,
+    sockets: tauri::State<'_, OutputWebSocketMap>,
+) -> Result<String, String> {
+    let socket_map = sockets.inner().clone();
+    let mut sockets = sockets.lock().await;
+    if let Some(existing) = sockets.get(&connection_id) {
+        return Err(format!(
+            "Output of '{}' is already mirrored at {}",
+            connection_id, existing.url
+        ));
+    }
+
+    let output = output_stream(&connection_id, &state).await?;
+
+    let host = if allow_remote.unwrap_or(false) { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = TcpListener::bind((host, port))
+        .await
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", host, port, e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    let path: Arc<str> = format!("/{}", uuid::Uuid::new_v4().simple()).into();
+    let url = format!("ws://{}{}", address, path);
+
+    let (mirror, _) = broadcast::channel(CLIENT_BUFFER_LINES);
+    output.set_mirror(Some(mirror));
+    let accept_task = tauri::async_runtime::spawn(accept_clients(listener, output.clone(), path, socket_map));
+
+    sockets.insert(
+        connection_id,
+        OutputWebSocket {
+            url: url.clone(),
+            output,
+            accept_task,
+        },
+    );
+
+    Ok(url)
+}
+
+/// Stops mirroring a connection's output and closes every client. Returns
+/// `false` if no mirror was running.
+#[tauri::command]
+pub async fn stop_output_websocket(
+    connection_id: String,
+    sockets: tauri::State<'_, OutputWebSocketMap>,
+) -> Result<bool, String> {
+    let Some(socket) = sockets.lock().await.remove(&connection_id) else {
+        return Ok(false);
+    };
+
+    socket.accept_task.abort();
+    socket.output.set_mirror(None);
+    Ok(true)
+}
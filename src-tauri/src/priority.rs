@@ -0,0 +1,97 @@
+use serde::Deserialize;
+
+use crate::process_manager::ProcessMap;
+
+/// Scheduling priority for a spawned process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessPriority {
+    Low,
+    Normal,
+    High,
+}
+
+/// Sets the niceness of `pid`. Raising priority (`High`) usually needs
+/// elevated privileges and fails with a permission error otherwise.
+#[cfg(unix)]
+pub fn apply_priority(pid: u32, priority: ProcessPriority) -> Result<(), String> {
+    let nice = match priority {
+        ProcessPriority::Low => 10,
+        ProcessPriority::Normal => 0,
+        ProcessPriority::High => -5,
+    };
+
+    // SAFETY: setpriority only reads its integer arguments
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) };
+    if result != 0 {
+        return Err(format!(
+            "Failed to set priority of process {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sets the priority class of `pid`.
+#[cfg(windows)]
+pub fn apply_priority(pid: u32, priority: ProcessPriority) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS,
+        NORMAL_PRIORITY_CLASS, PROCESS_SET_INFORMATION,
+    };
+
+    let class = match priority {
+        ProcessPriority::Low => BELOW_NORMAL_PRIORITY_CLASS,
+        ProcessPriority::Normal => NORMAL_PRIORITY_CLASS,
+        ProcessPriority::High => ABOVE_NORMAL_PRIORITY_CLASS,
+    };
+
+    // SAFETY: the handle is checked before use and closed exactly once
+    unsafe {
+        let handle = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+        if handle == 0 {
+            return Err(format!(
+                "Failed to open process {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let ok = SetPriorityClass(handle, class);
+        let error = std::io::Error::last_os_error();
+        CloseHandle(handle);
+
+        if ok == 0 {
+            return Err(format!("Failed to set priority of process {}: {}", pid, error));
+        }
+    }
+
+    Ok(())
+}
+
+/// Changes the scheduling priority of a running process.
+#[tauri::command]
+pub async fn set_process_priority(
+    connection_id: String,
+    priority: ProcessPriority,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let mut processes = state.lock().await;
+
+    let process = processes
+        .get_mut(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    let pid = process
+        .child
+        .id()
+        .ok_or_else(|| format!("Process '{}' has already exited", connection_id))?;
+
+    apply_priority(pid, priority)?;
+    process.options.priority = Some(priority);
+
+    Ok(())
+}
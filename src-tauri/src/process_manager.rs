@@ -1,23 +1,695 @@
+use base64::Engine;
+use futures_util::future::Either;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::sync::Mutex;
 
+use crate::priority::{apply_priority, ProcessPriority};
+use crate::requests::{self, CancelFlag, RequestMap};
+use crate::process_output::{LineReader, OutputStream, ProcessLog, ReadySignal, StderrTarget};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub connection_id: String,
     pub process_type: String,
     pub command: String,
     pub args: Vec<String>,
+    /// File every stdout/stderr line is appended to, if logging is enabled.
+    #[serde(default)]
+    pub log_file: Option<String>,
+    /// File stdout is redirected to, when spawned with `stdout_file`.
+    #[serde(default)]
+    pub stdout_file: Option<String>,
+    /// File stderr is redirected to, when spawned with `stderr_file`.
+    #[serde(default)]
+    pub stderr_file: Option<String>,
+    /// Bytes written to stdin since spawn or the last counter reset.
+    #[serde(default)]
+    pub bytes_sent: u64,
+    /// Bytes read from stdout since spawn or the last counter reset.
+    #[serde(default)]
+    pub bytes_received: u64,
+    /// Outgoing rate limit, when `max_messages_per_sec` was set.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitState>,
+    /// Milliseconds from spawn to the `initialize` response, or to the first
+    /// stdout line for processes that never complete a handshake.
+    #[serde(default)]
+    pub startup_ms: Option<u64>,
+    /// Whether output has matched `ready_pattern`; unset without a pattern.
+    #[serde(default)]
+    pub ready: Option<bool>,
+}
+
+/// Options accepted by the spawn commands. Every field is optional so the
+/// frontend only sends what it needs.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SpawnOptions {
+    /// Kill the process after this long without send/read activity.
+    pub idle_timeout_ms: Option<u64>,
+    /// Maximum stdout lines emitted per second when streaming; lines beyond
+    /// the rate are coalesced into batch events.
+    pub max_emit_rate: Option<u32>,
+    /// Append every stdout/stderr line to this file.
+    pub log_to_file: Option<String>,
+    /// Scheduling priority applied right after spawning.
+    pub priority: Option<ProcessPriority>,
+    /// Upper bound on output buffered while streaming is paused, in bytes.
+    /// The oldest lines are dropped first.
+    pub max_output_bytes: Option<usize>,
+    /// `.env` file merged into the process environment. A missing file is
+    /// ignored; a malformed one fails the spawn.
+    pub env_file: Option<String>,
+    /// Maximum messages written to stdin per second. Short bursts up to this
+    /// many messages are allowed.
+    pub max_messages_per_sec: Option<u32>,
+    /// What happens to a message over the rate. Defaults to `delay`.
+    pub rate_limit_mode: Option<RateLimitMode>,
+    /// Keep a transcript in the sessions directory even without
+    /// `log_to_file`, so it can be reopened with `read_session`.
+    pub persist_transcript: Option<bool>,
+    /// Project whose `node_modules/.bin` is searched for the command before
+    /// `PATH`, for locally installed MCP servers.
+    pub project_root: Option<String>,
+    /// Name the process sees as `argv[0]`, for multi-call binaries such as
+    /// busybox. Unix only.
+    pub arg0: Option<String>,
+    /// Write stdout straight to this file (appending) instead of a pipe. No
+    /// reader runs for it; tail the file with `tail_file`.
+    pub stdout_file: Option<String>,
+    /// Write stderr straight to this file (appending) instead of a pipe.
+    pub stderr_file: Option<String>,
+    /// `spawn_mcp_server` only: `true` runs the command line through
+    /// `sh -c` (`cmd /C` on Windows) so shell syntax in the args works;
+    /// `false` spawns directly even if the args look like shell syntax.
+    /// Unset also spawns directly, but emits a `spawn-shell-syntax` warning
+    /// when args look like shell syntax.
+    pub use_shell: Option<bool>,
+    /// Regex matched against each stdout/stderr line read in the
+    /// background; the first match marks the process ready.
+    pub ready_pattern: Option<String>,
+    /// Regex an output line must match to be emitted while streaming.
+    /// Every line is still logged. Changed with `set_output_filter`.
+    pub filter_pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitMode {
+    /// Wait until the message is allowed.
+    #[default]
+    Delay,
+    /// Fail the send immediately.
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitState {
+    pub max_messages_per_sec: u32,
+    pub mode: RateLimitMode,
+    /// Messages that can be sent right now without waiting.
+    pub available: f64,
+    /// Sends rejected in `reject` mode.
+    pub rejected: u64,
+}
+
+/// Token bucket limiting outgoing messages on one connection.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: u32,
+    mode: RateLimitMode,
+    tokens: f64,
+    refilled_at: Instant,
+    rejected: u64,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32, mode: RateLimitMode) -> Self {
+        Self {
+            rate: rate.max(1),
+            mode,
+            tokens: rate.max(1) as f64,
+            refilled_at: Instant::now(),
+            rejected: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        self.refilled_at = now;
+    }
+
+    /// Takes a token. In `delay` mode an empty bucket lends one against
+    /// future refills and returns when it's due, so the caller can sleep
+    /// without holding the limiter (or the process map it lives in).
+    fn reserve(&mut self) -> Result<Option<Instant>, String> {
+        self.refill();
+        if self.tokens < 1.0 && self.mode == RateLimitMode::Reject {
+            self.rejected += 1;
+            return Err(format!(
+                "Rate limit exceeded ({} messages per second)",
+                self.rate
+            ));
+        }
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            return Ok(None);
+        }
+        let wait = -self.tokens / self.rate as f64;
+        Ok(Some(self.refilled_at + Duration::from_secs_f64(wait)))
+    }
+
+    fn state(&self) -> RateLimitState {
+        let elapsed = self.refilled_at.elapsed().as_secs_f64();
+        RateLimitState {
+            max_messages_per_sec: self.rate,
+            mode: self.mode,
+            available: (self.tokens + elapsed * self.rate as f64).clamp(0.0, self.rate as f64),
+            rejected: self.rejected,
+        }
+    }
+}
+
+/// Time of the last send/read on a connection, shared with its idle watchdog.
+#[derive(Debug, Clone)]
+pub struct Activity(Arc<std::sync::Mutex<Instant>>);
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Activity {
+    pub fn new() -> Self {
+        Self(Arc::new(std::sync::Mutex::new(Instant::now())))
+    }
+
+    pub fn touch(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+/// Stdin/stdout byte totals, shared with the background output reader.
+#[derive(Debug, Clone, Default)]
+pub struct ByteCounters {
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+}
+
+impl ByteCounters {
+    pub fn add_sent(&self, bytes: usize) {
+        self.sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_received(&self, bytes: usize) {
+        self.received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.sent.store(0, Ordering::Relaxed);
+        self.received.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Resource uris subscribed to with `subscribe_mcp_resource`, shared with
+/// the background output reader.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceSubscriptions(Arc<std::sync::Mutex<HashSet<String>>>);
+
+impl ResourceSubscriptions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn contains(&self, uri: &str) -> bool {
+        self.lock().contains(uri)
+    }
+}
+
+/// Emits server-initiated messages the backend knows how to dispatch:
+/// `resources/updated` notifications for subscribed uris become
+/// `mcp-resource-updated`, and `sampling/createMessage` requests become
+/// `mcp-sampling-request`. Returns whether `line` was routed; anything else
+/// is left to the caller.
+pub fn route_server_message(
+    app: &tauri::AppHandle,
+    connection_id: &str,
+    subscriptions: &ResourceSubscriptions,
+    line: &str,
+) -> bool {
+    let Ok(message) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+        return false;
+    };
+
+    match message.get("method").and_then(|m| m.as_str()) {
+        Some("notifications/resources/updated") => {
+            let Some(uri) = message.pointer("/params/uri").and_then(|u| u.as_str()) else {
+                return false;
+            };
+            if !subscriptions.contains(uri) {
+                return false;
+            }
+            let _ = app.emit(
+                "mcp-resource-updated",
+                serde_json::json!({ "connection_id": connection_id, "uri": uri }),
+            );
+            true
+        }
+        Some("sampling/createMessage") => {
+            let Some(request_id) = message.get("id") else {
+                return false;
+            };
+            let _ = app.emit(
+                "mcp-sampling-request",
+                serde_json::json!({
+                    "connection_id": connection_id,
+                    "request_id": request_id,
+                    "params": message.get("params").cloned().unwrap_or_default(),
+                }),
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+/// A tool from an MCP server's `tools/list` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// JSON Schema of the tool's arguments.
+    #[serde(default)]
+    pub input_schema: serde_json::Value,
+}
+
+/// A resource from an MCP server's `resources/list` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// One entry of a `resources/read` response as the server sends it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawResourceContents {
+    uri: String,
+    #[serde(default)]
+    mime_type: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    /// Base64-encoded binary content.
+    #[serde(default)]
+    blob: Option<String>,
+}
+
+/// Resource contents with `blob` already decoded. Exactly one of `text` and
+/// `blob` is set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResourceContents {
+    pub uri: String,
+    pub mime_type: Option<String>,
+    pub text: Option<String>,
+    pub blob: Option<Vec<u8>>,
+}
+
+/// A process's stdout reader. It has its own lock so a read blocked waiting
+/// for a quiet server doesn't hold the `ProcessMap` lock.
+pub type StdoutReader = Arc<Mutex<LineReader>>;
+pub type StdinWriter = Arc<Mutex<ChildStdin>>;
+
+/// Handles cloned out of a process entry so a request can write and wait for
+/// its response without holding the process map lock, which would stall
+/// every other connection for as long as the server takes to answer.
+#[derive(Clone)]
+struct Connection {
+    connection_id: String,
+    stdin: Option<StdinWriter>,
+    stdout: Option<StdoutReader>,
+    subscriptions: ResourceSubscriptions,
+    activity: Activity,
+    counters: ByteCounters,
+    log: Option<Arc<ProcessLog>>,
+    ready: Option<Arc<ReadySignal>>,
+}
+
+impl Connection {
+    fn stdout(&self) -> Result<&StdoutReader, String> {
+        self.stdout
+            .as_ref()
+            .ok_or_else(|| "Process stdout not available".to_string())
+    }
+
+    /// Writes one newline-terminated message to stdin.
+    async fn write(&self, message: &str) -> Result<(), String> {
+        let mut stdin = self
+            .stdin
+            .as_ref()
+            .ok_or_else(|| "Process stdin not available".to_string())?
+            .lock()
+            .await;
+
+        stdin
+            .write_all(format!("{}\n", message).as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+        drop(stdin);
+
+        if let Some(log) = &self.log {
+            log.write_line("stdin", message).await;
+        }
+        self.activity.touch();
+        self.counters.add_sent(message.len() + 1);
+        Ok(())
+    }
 }
 
 pub struct ManagedProcess {
     pub info: ProcessInfo,
     pub child: Child,
-    pub stdin: Option<ChildStdin>,
-    pub stdout_reader: Option<BufReader<ChildStdout>>,
+    /// Child PID, kept after the child is reaped since it also identifies
+    /// the process group its descendants may still be running in.
+    pub pid: Option<u32>,
+    pub stdin: Option<StdinWriter>,
+    pub stdout_reader: Option<StdoutReader>,
+    /// Set when streaming starts so the stderr reader emits events.
+    pub stderr_target: StderrTarget,
+    /// Server capabilities from the `initialize` response, once received.
+    pub capabilities: Option<serde_json::Value>,
+    /// Id of the `initialize` request we're waiting on a response for.
+    pub pending_initialize_id: Option<serde_json::Value>,
+    /// Tool catalog from `tools/list`, cleared when the server sends
+    /// `notifications/tools/list_changed`.
+    pub tools: Option<Vec<McpTool>>,
+    pub subscriptions: ResourceSubscriptions,
+    /// Background output reader, once `stream_process_output` has started.
+    pub output: Option<Arc<OutputStream>>,
+    pub activity: Activity,
+    pub counters: ByteCounters,
+    pub options: SpawnOptions,
+    /// Variables set on top of the app's environment (from `env_file`).
+    pub env: HashMap<String, String>,
+    pub log: Option<Arc<ProcessLog>>,
+    /// Exit code once the child has exited and been reaped (`None` inside
+    /// means it was killed by a signal).
+    pub exit_code: Option<Option<i32>>,
+    pub rate_limiter: Option<RateLimiter>,
+    pub started_at: Instant,
+    pub first_output_after: Option<Duration>,
+    pub initialized_after: Option<Duration>,
+    /// Set when spawned with `ready_pattern`.
+    pub ready: Option<Arc<ReadySignal>>,
+}
+
+impl ManagedProcess {
+    /// Records the end of the process's session, if it has a transcript.
+    async fn end_session(&self) {
+        if let Some(ready) = &self.ready {
+            ready.mark_exited();
+        }
+        if self.info.log_file.is_some() {
+            crate::sessions::finish(&self.info.connection_id, self.exit_code.flatten()).await;
+        }
+    }
+
+    /// Process info with the current byte counters filled in.
+    pub fn info_snapshot(&self) -> ProcessInfo {
+        ProcessInfo {
+            bytes_sent: self.counters.sent.load(Ordering::Relaxed),
+            bytes_received: self.counters.received.load(Ordering::Relaxed),
+            rate_limit: self.rate_limiter.as_ref().map(RateLimiter::state),
+            startup_ms: self
+                .initialized_after
+                .or(self.first_output_after)
+                .map(|elapsed| elapsed.as_millis() as u64),
+            ready: self.ready.as_ref().map(|ready| ready.is_ready()),
+            ..self.info.clone()
+        }
+    }
+
+    /// Inspects an outgoing message so the handshake can be tracked without
+    /// the frontend reporting it separately.
+    fn record_outgoing(&mut self, message: &str) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(message) else {
+            return;
+        };
+
+        if value.get("method").and_then(|m| m.as_str()) == Some("initialize") {
+            self.pending_initialize_id = value.get("id").cloned();
+        }
+    }
+
+    fn connection(&self) -> Connection {
+        Connection {
+            connection_id: self.info.connection_id.clone(),
+            stdin: self.stdin.clone(),
+            stdout: self.stdout_reader.clone(),
+            subscriptions: self.subscriptions.clone(),
+            activity: self.activity.clone(),
+            counters: self.counters.clone(),
+            log: self.log.clone(),
+            ready: self.ready.clone(),
+        }
+    }
+
+    /// Notes when stdout first produced anything, for `startup_ms`.
+    pub fn record_output_started(&mut self) {
+        if self.first_output_after.is_none() {
+            self.first_output_after = Some(self.started_at.elapsed());
+        }
+    }
+
+    /// Inspects an incoming message: stores the server capabilities when it
+    /// answers the pending `initialize` request, and drops the cached tool
+    /// list when the server says it changed.
+    fn record_incoming(&mut self, message: &str) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(message.trim()) else {
+            return;
+        };
+
+        if value.get("method").and_then(|m| m.as_str()) == Some("notifications/tools/list_changed") {
+            self.tools = None;
+            return;
+        }
+
+        let Some(pending_id) = self.pending_initialize_id.as_ref() else {
+            return;
+        };
+        if value.get("id") != Some(pending_id) {
+            return;
+        }
+
+        self.pending_initialize_id = None;
+        self.initialized_after = Some(self.started_at.elapsed());
+        if let Some(capabilities) = value.get("result").and_then(|r| r.get("capabilities")) {
+            self.capabilities = Some(capabilities.clone());
+        }
+    }
+}
+
+/// Looks a process up and clones its handles, holding the map lock only for
+/// the lookup.
+async fn connection(state: &ProcessMap, connection_id: &str) -> Result<Connection, String> {
+    state
+        .lock()
+        .await
+        .get(connection_id)
+        .map(ManagedProcess::connection)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))
+}
+
+/// Writes one message to a process's stdin. The map is locked only for
+/// rate limiting and handshake tracking; a rate-limit delay and the write
+/// itself happen after it's released.
+async fn send_message(state: &ProcessMap, connection_id: &str, message: &str) -> Result<(), String> {
+    let (connection, send_at) = {
+        let mut processes = state.lock().await;
+        let process = processes
+            .get_mut(connection_id)
+            .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+        let send_at = match process.rate_limiter.as_mut() {
+            Some(limiter) => limiter.reserve()?,
+            None => None,
+        };
+        process.record_outgoing(message);
+        (process.connection(), send_at)
+    };
+
+    if let Some(send_at) = send_at {
+        tokio::time::sleep_until(send_at.into()).await;
+    }
+    connection.write(message).await
+}
+
+/// Bookkeeping for every stdout line read on the request/response path.
+async fn handle_incoming(state: &ProcessMap, connection: &Connection, line: &str) {
+    connection.activity.touch();
+    connection.counters.add_received(line.len());
+    if let Some(log) = &connection.log {
+        log.write_line("stdout", line).await;
+    }
+    if let Some(ready) = &connection.ready {
+        ready.check_line(line);
+    }
+
+    // The process may have been killed while we were reading
+    if let Some(process) = state.lock().await.get_mut(&connection.connection_id) {
+        if !line.is_empty() {
+            process.record_output_started();
+        }
+        process.record_incoming(line);
+    }
+}
+
+/// Callback for lines read while waiting for a particular response.
+type UnmatchedLine<'a> = dyn FnMut(&Connection, &str) + Send + 'a;
+
+/// Routes server requests and resource updates read while waiting for a
+/// response, and emits anything else as an `mcp-message` event.
+fn forward_to_frontend(app: &tauri::AppHandle, connection: &Connection, line: &str) {
+    if route_server_message(app, &connection.connection_id, &connection.subscriptions, line) {
+        return;
+    }
+    let _ = app.emit(
+        "mcp-message",
+        serde_json::json!({ "connection_id": connection.connection_id, "message": line }),
+    );
+}
+
+/// Reads lines until a JSON-RPC message whose `id` equals `match_id`
+/// arrives. Other lines go to `on_unmatched`.
+async fn read_until_id(
+    state: &ProcessMap,
+    connection: &Connection,
+    reader: &mut LineReader,
+    match_id: &serde_json::Value,
+    on_unmatched: &mut UnmatchedLine<'_>,
+) -> Result<String, String> {
+    loop {
+        let mut line = String::new();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+        if bytes_read == 0 {
+            return Err("Process stdout closed".to_string());
+        }
+
+        handle_incoming(state, connection, &line).await;
+
+        let is_match = serde_json::from_str::<serde_json::Value>(line.trim())
+            .map(|message| message.get("id") == Some(match_id))
+            .unwrap_or(false);
+        if is_match {
+            return Ok(line);
+        }
+        on_unmatched(connection, &line);
+    }
+}
+
+/// Sends `message` and reads until the response with `id`. The reader is
+/// held from before the write so concurrent requests on one connection can't
+/// read each other's responses; the map lock is never held while waiting.
+async fn exchange(
+    state: &ProcessMap,
+    connection_id: &str,
+    message: &str,
+    id: &serde_json::Value,
+    on_unmatched: &mut UnmatchedLine<'_>,
+) -> Result<String, String> {
+    let connection = connection(state, connection_id).await?;
+    let mut reader = connection.stdout()?.lock().await;
+
+    send_message(state, connection_id, message).await?;
+    read_until_id(state, &connection, &mut reader, id, on_unmatched).await
+}
+
+/// Sends a JSON-RPC request and waits up to `timeout` for its response,
+/// returning the `result`. An `error` response becomes the error string.
+async fn request(
+    state: &ProcessMap,
+    connection_id: &str,
+    method: &str,
+    params: serde_json::Value,
+    timeout: Duration,
+    on_unmatched: &mut UnmatchedLine<'_>,
+) -> Result<serde_json::Value, String> {
+    let id = serde_json::Value::String(format!("beaki-{}", uuid::Uuid::new_v4()));
+    let message = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params,
+    });
+
+    let line = tokio::time::timeout(
+        timeout,
+        exchange(state, connection_id, &message.to_string(), &id, on_unmatched),
+    )
+    .await
+    .map_err(|_| format!("{} timed out after {}ms", method, timeout.as_millis()))??;
+
+    let mut response: serde_json::Value = serde_json::from_str(line.trim())
+        .map_err(|e| format!("Invalid JSON from process: {}", e))?;
+
+    if let Some(error) = response.get("error") {
+        let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or_default();
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Unknown error");
+        return Err(format!("{} failed ({}): {}", method, code, message));
+    }
+
+    response
+        .get_mut("result")
+        .map(serde_json::Value::take)
+        .ok_or_else(|| format!("Response to {} has no result", method))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` when the process was terminated by a signal.
+    pub exit_code: Option<i32>,
+    /// Output hit `max_output_bytes`; capture stopped and the process was killed.
+    pub truncated: bool,
+}
+
+/// A piece of output from `spawn_and_run_streamed`, as it was read.
+#[derive(Debug, Clone, Serialize)]
+struct RunChunk<'a> {
+    /// `stdout` or `stderr`.
+    stream: &'static str,
+    data: &'a str,
 }
 
 pub type ProcessMap = Arc<Mutex<HashMap<String, ManagedProcess>>>;
@@ -26,54 +698,348 @@ pub fn create_process_map() -> ProcessMap {
     Arc::new(Mutex::new(HashMap::new()))
 }
 
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum SpawnError {
+    /// The binary exists but lacks the execute permission; the frontend can
+    /// offer `make_executable`.
+    NotExecutable { path: String },
+    /// The resource guard in settings is on and memory or CPU is exhausted.
+    ResourcesExhausted {
+        reason: String,
+        usage: crate::resources::ResourceUsage,
+    },
+    Failed { message: String },
+}
+
+impl From<String> for SpawnError {
+    fn from(message: String) -> Self {
+        SpawnError::Failed { message }
+    }
+}
+
+/// After a `PermissionDenied` spawn error, finds the file that was refused:
+/// `program` itself when it's a path, otherwise the first `PATH` entry with
+/// that name that isn't executable.
+#[cfg(unix)]
+fn find_not_executable(program: &std::path::Path) -> Option<std::path::PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let is_not_executable = |path: &std::path::Path| {
+        std::fs::metadata(path)
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 == 0)
+            .unwrap_or(false)
+    };
+
+    if program.components().count() > 1 {
+        return Some(program.to_path_buf()).filter(|p| is_not_executable(p));
+    }
+
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|p| is_not_executable(p))
+}
+
+#[cfg(not(unix))]
+fn find_not_executable(_program: &std::path::Path) -> Option<std::path::PathBuf> {
+    None
+}
+
+/// Operators that only mean something to a shell, wherever they appear in
+/// an arg. Pipes and redirects are only flagged as standalone args, since a
+/// bare `|` or `>` inside a regex or URL is common.
+const SHELL_OPERATORS: &[&str] = &["&&", "||", "$(", "`"];
+const SHELL_OPERATOR_ARGS: &[&str] = &["|", ">"];
+
+fn has_shell_syntax(arg: &str) -> bool {
+    SHELL_OPERATOR_ARGS.contains(&arg) || SHELL_OPERATORS.iter().any(|op| arg.contains(op))
+}
+
+fn shell_syntax_args(args: &[String]) -> Vec<String> {
+    args.iter().filter(|arg| has_shell_syntax(arg)).cloned().collect()
+}
+
+/// Characters `cmd` acts on outside quotes, and `^` escapes.
+const CMD_METACHARS: &[char] = &['"', '&', '|', '<', '>', '^', '%', '!', '(', ')'];
+
+/// Quotes an arg for `cmd /C`: quoted for the program's own argument
+/// parsing, then every metacharacter, quotes included, `^`-escaped so `cmd`
+/// passes it through rather than acting on it.
+fn quote_cmd_arg(arg: &str) -> String {
+    let quoted = format!("\"{}\"", arg.replace('"', "\"\""));
+    let mut escaped = String::with_capacity(quoted.len() * 2);
+    for c in quoted.chars() {
+        if CMD_METACHARS.contains(&c) {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Quotes an arg for the platform shell.
+fn quote_arg(arg: &str) -> String {
+    if cfg!(windows) {
+        quote_cmd_arg(arg)
+    } else {
+        shell_words::quote(arg).into_owned()
+    }
+}
+
+/// Wraps a command line in the platform shell. Args with shell syntax are
+/// kept as written so it's interpreted; the rest are quoted so paths with
+/// spaces or quotes survive.
+fn shell_command(command: String, args: Vec<String>) -> (String, Vec<String>) {
+    let line = std::iter::once(quote_arg(&command))
+        .chain(args.iter().map(|arg| if has_shell_syntax(arg) { arg.clone() } else { quote_arg(arg) }))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cfg!(windows) {
+        ("cmd".to_string(), vec!["/C".to_string(), line])
+    } else {
+        ("sh".to_string(), vec!["-c".to_string(), line])
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ShellSyntaxWarning {
+    connection_id: String,
+    args: Vec<String>,
+    hint: String,
+}
+
+/// Spawns an MCP server. Configs copied from a terminal sometimes carry
+/// shell syntax (`&&`, pipes, `$(...)`) that would reach the server as
+/// literal arguments; unless `use_shell` says how to handle them, such args
+/// are passed through and a `spawn-shell-syntax` warning event is emitted.
 #[tauri::command]
 pub async fn spawn_mcp_server(
     command: String,
     args: Vec<String>,
+    options: Option<SpawnOptions>,
+    app: tauri::AppHandle,
     state: tauri::State<'_, ProcessMap>,
-) -> Result<String, String> {
-    spawn_process("mcp".to_string(), command, args, state).await
+) -> Result<String, SpawnError> {
+    let options = options.unwrap_or_default();
+
+    let flagged = match options.use_shell {
+        None => shell_syntax_args(&args),
+        Some(_) => Vec::new(),
+    };
+    let hint = format!(
+        "{} is started without a shell, so shell operators in its arguments are passed through \
+         literally. Set use_shell to run it through a shell, or to false to silence this warning.",
+        command
+    );
+    let (command, args) = match options.use_shell {
+        Some(true) => shell_command(command, args),
+        _ => (command, args),
+    };
+
+    let connection_id = spawn_process("mcp".to_string(), command, args, options, app.clone(), state).await?;
+    if !flagged.is_empty() {
+        let _ = app.emit(
+            "spawn-shell-syntax",
+            ShellSyntaxWarning {
+                connection_id: connection_id.clone(),
+                args: flagged,
+                hint,
+            },
+        );
+    }
+    Ok(connection_id)
 }
 
 #[tauri::command]
 pub async fn spawn_cli_agent(
     tool: String,
     args: Vec<String>,
+    options: Option<SpawnOptions>,
+    app: tauri::AppHandle,
     state: tauri::State<'_, ProcessMap>,
-) -> Result<String, String> {
-    spawn_process("cli".to_string(), tool, args, state).await
+) -> Result<String, SpawnError> {
+    spawn_process("cli".to_string(), tool, args, options.unwrap_or_default(), app, state).await
+}
+
+/// Opens a file a child's output is redirected to, appending so a restarted
+/// process doesn't wipe its earlier output.
+async fn redirect_file(path: &str) -> Result<std::process::Stdio, String> {
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| format!("Failed to open output file {}: {}", path, e))?;
+    Ok(file.into_std().await.into())
+}
+
+/// Finds a bare command (no path separators) in `project_root`'s
+/// `node_modules/.bin`, where npm puts the binaries of local packages.
+pub fn resolve_local_bin(command: &str, project_root: &std::path::Path) -> Option<std::path::PathBuf> {
+    if command.contains(['/', '\\']) {
+        return None;
+    }
+
+    let bin_dir = project_root.join("node_modules").join(".bin");
+    // npm writes `.cmd` shims on Windows; the extensionless file is a shell script
+    let candidates = if cfg!(windows) {
+        vec![format!("{}.cmd", command), format!("{}.exe", command)]
+    } else {
+        vec![command.to_string()]
+    };
+
+    candidates
+        .into_iter()
+        .map(|name| bin_dir.join(name))
+        .find(|path| path.is_file())
 }
 
 async fn spawn_process(
     process_type: String,
     command: String,
     args: Vec<String>,
+    options: SpawnOptions,
+    app: tauri::AppHandle,
     state: tauri::State<'_, ProcessMap>,
-) -> Result<String, String> {
+) -> Result<String, SpawnError> {
+    let settings = crate::settings::load_settings().await?;
+    if state.lock().await.len() >= settings.max_processes {
+        return Err(format!(
+            "Process limit reached ({} running); increase max_processes in settings",
+            settings.max_processes
+        )
+        .into());
+    }
+
+    if settings.resource_check_enabled {
+        let usage = crate::resources::current_usage().await?;
+        if let Some(reason) = crate::resources::exhausted_reason(&usage, &settings) {
+            return Err(SpawnError::ResourcesExhausted { reason, usage });
+        }
+    }
+
     // Generate unique connection ID
     let connection_id = uuid::Uuid::new_v4().to_string();
 
-    // Spawn the process
-    let mut child = Command::new(&command)
+    crate::process_output::compile_filter(options.filter_pattern.as_deref())?;
+
+    let ready = match &options.ready_pattern {
+        Some(pattern) => {
+            let pattern = regex::Regex::new(pattern)
+                .map_err(|e| format!("Invalid ready_pattern '{}': {}", pattern, e))?;
+            Some(Arc::new(ReadySignal::new(app.clone(), connection_id.clone(), pattern)))
+        }
+        None => None,
+    };
+
+    let env = match &options.env_file {
+        Some(path) => {
+            let env = crate::env_file::load_env_file(path).await?;
+            if !env.errors.is_empty() {
+                let details: Vec<String> = env
+                    .errors
+                    .iter()
+                    .map(|e| format!("line {}: {}", e.line, e.message))
+                    .collect();
+                return Err(format!("Invalid env file {}: {}", path, details.join("; ")).into());
+            }
+            env.vars
+        }
+        None => HashMap::new(),
+    };
+
+    let stdout = match &options.stdout_file {
+        Some(path) => redirect_file(path).await?,
+        None => std::process::Stdio::piped(),
+    };
+    let stderr = match &options.stderr_file {
+        Some(path) => redirect_file(path).await?,
+        None => std::process::Stdio::piped(),
+    };
+
+    let program = options
+        .project_root
+        .as_deref()
+        .and_then(|root| resolve_local_bin(&command, std::path::Path::new(root)))
+        .unwrap_or_else(|| std::path::PathBuf::from(&command));
+
+    // Spawn the process. Dropping an entry kills the child, and tokio reaps
+    // dropped children in the background, so none outlive their entry.
+    let mut command_builder = Command::new(&program);
+    command_builder
         .args(&args)
+        .envs(&env)
         .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn process '{}': {}", command, e))?;
+        .stdout(stdout)
+        .stderr(stderr)
+        .kill_on_drop(true);
+    in_new_process_group(&mut command_builder);
+
+    if let Some(arg0) = &options.arg0 {
+        #[cfg(unix)]
+        command_builder.arg0(arg0);
+        #[cfg(not(unix))]
+        return Err(format!("arg0 '{}' can only be set on Unix", arg0).into());
+    }
 
-    // Take ownership of stdin and stdout
+    let mut child = command_builder.spawn().map_err(|e| {
+        let not_executable = (e.kind() == std::io::ErrorKind::PermissionDenied)
+            .then(|| find_not_executable(&program))
+            .flatten();
+        match not_executable {
+            Some(path) => SpawnError::NotExecutable {
+                path: path.to_string_lossy().into_owned(),
+            },
+            None => format!("Failed to spawn process '{}': {}", command, e).into(),
+        }
+    })?;
+
+    if let (Some(priority), Some(pid)) = (options.priority, child.id()) {
+        if let Err(e) = apply_priority(pid, priority) {
+            let _ = child.start_kill();
+            return Err(e.into());
+        }
+    }
+
+    // Take ownership of stdin and stdout; redirected output has no pipe
     let stdin = child
         .stdin
         .take()
         .ok_or_else(|| "Failed to capture stdin".to_string())?;
 
-    let stdout = child
+    let stdout_reader = child
         .stdout
         .take()
-        .ok_or_else(|| "Failed to capture stdout".to_string())?;
+        .map(|stdout| Arc::new(Mutex::new(LineReader::new(stdout))));
+    let stderr_reader = child.stderr.take().map(BufReader::new);
+
+    let transcript = match (&options.log_to_file, options.persist_transcript.unwrap_or(false)) {
+        (Some(path), _) => Some(std::path::PathBuf::from(path)),
+        (None, true) => Some(crate::sessions::transcript_path(&connection_id)?),
+        (None, false) => None,
+    };
+
+    let log = match &transcript {
+        Some(path) => {
+            crate::sessions::start(&connection_id, &process_type, &command, &args, path).await?;
+            Some(Arc::new(ProcessLog::open(&path.to_string_lossy()).await?))
+        }
+        None => None,
+    };
 
-    let stdout_reader = BufReader::new(stdout);
+    // Stderr isn't read on the request/response path, so it gets its own
+    // reader straight away
+    let stderr_target = StderrTarget::default();
+    if let Some(reader) = stderr_reader {
+        tauri::async_runtime::spawn(crate::process_output::pump_stderr(
+            app.clone(),
+            log.clone(),
+            stderr_target.clone(),
+            ready.clone(),
+            reader,
+        ));
+    }
 
     // Create process info
     let info = ProcessInfo {
@@ -81,122 +1047,1593 @@ async fn spawn_process(
         process_type: process_type.clone(),
         command: command.clone(),
         args: args.clone(),
+        log_file: transcript.map(|path| path.to_string_lossy().into_owned()),
+        stdout_file: options.stdout_file.clone(),
+        stderr_file: options.stderr_file.clone(),
+        bytes_sent: 0,
+        bytes_received: 0,
+        rate_limit: None,
+        startup_ms: None,
+        ready: None,
     };
 
     // Store the process
     let managed_process = ManagedProcess {
         info,
+        pid: child.id(),
         child,
-        stdin: Some(stdin),
-        stdout_reader: Some(stdout_reader),
+        stdin: Some(Arc::new(Mutex::new(stdin))),
+        stdout_reader,
+        stderr_target,
+        capabilities: None,
+        pending_initialize_id: None,
+        tools: None,
+        subscriptions: ResourceSubscriptions::default(),
+        output: None,
+        activity: Activity::new(),
+        counters: ByteCounters::default(),
+        options: options.clone(),
+        env,
+        log,
+        exit_code: None,
+        rate_limiter: options
+            .max_messages_per_sec
+            .map(|rate| RateLimiter::new(rate, options.rate_limit_mode.unwrap_or_default())),
+        started_at: Instant::now(),
+        first_output_after: None,
+        initialized_after: None,
+        ready,
     };
 
+    let activity = managed_process.activity.clone();
+
     let mut processes = state.lock().await;
     processes.insert(connection_id.clone(), managed_process);
+    drop(processes);
+
+    if let Some(timeout_ms) = options.idle_timeout_ms {
+        tauri::async_runtime::spawn(watch_idle(
+            app,
+            state.inner().clone(),
+            connection_id.clone(),
+            activity,
+            Duration::from_millis(timeout_ms),
+        ));
+    }
 
     Ok(connection_id)
 }
 
-#[tauri::command]
-pub async fn send_mcp_message(
+/// Kills a process once it has gone `timeout` without send/read activity and
+/// emits `process-idle-killed`. Exits quietly if the process is removed first.
+async fn watch_idle(
+    app: tauri::AppHandle,
+    processes: ProcessMap,
     connection_id: String,
-    message: String,
-    state: tauri::State<'_, ProcessMap>,
-) -> Result<(), String> {
-    let mut processes = state.lock().await;
+    activity: Activity,
+    timeout: Duration,
+) {
+    loop {
+        let idle = activity.idle_for();
+        if idle < timeout {
+            tokio::time::sleep(timeout - idle).await;
+            continue;
+        }
 
-    let process = processes
-        .get_mut(&connection_id)
-        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+        let mut map = processes.lock().await;
 
-    let stdin = process
-        .stdin
-        .as_mut()
-        .ok_or_else(|| "Process stdin not available".to_string())?;
+        // Re-check under the lock so a last-moment send isn't killed
+        if activity.idle_for() < timeout {
+            continue;
+        }
 
-    // Write message to stdin with newline
-    let message_with_newline = format!("{}\n", message);
-    stdin
-        .write_all(message_with_newline.as_bytes())
-        .await
-        .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+        if let Some(mut process) = map.remove(&connection_id) {
+            drop(map);
+            let _ = terminate(&mut process).await;
+            let _ = app.emit(
+                "process-idle-killed",
+                serde_json::json!({ "connection_id": connection_id, "idle_ms": timeout.as_millis() as u64 }),
+            );
+        }
+        return;
+    }
+}
 
-    stdin
-        .flush()
-        .await
-        .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+/// Reads `reader` to the end into `buffer`, passing each captured chunk to
+/// `on_chunk` as it arrives. With a limit, `used` counts bytes across every
+/// stream of the process and reading stops once the total would exceed it;
+/// returns `true` in that case.
+async fn capture_output<R: AsyncRead + Unpin>(
+    mut reader: R,
+    buffer: &mut Vec<u8>,
+    used: &AtomicUsize,
+    limit: Option<usize>,
+    mut on_chunk: impl FnMut(&[u8]),
+) -> Result<bool, String> {
+    let mut chunk = vec![0u8; 8 * 1024];
 
-    Ok(())
+    loop {
+        let read = reader
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to collect output: {}", e))?;
+        if read == 0 {
+            return Ok(false);
+        }
+
+        if let Some(limit) = limit {
+            let before = used.fetch_add(read, Ordering::SeqCst);
+            if before + read > limit {
+                let remaining = limit.saturating_sub(before);
+                buffer.extend_from_slice(&chunk[..remaining]);
+                on_chunk(&chunk[..remaining]);
+                return Ok(true);
+            }
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        on_chunk(&chunk[..read]);
+    }
 }
 
-#[tauri::command]
-pub async fn read_mcp_response(
-    connection_id: String,
-    state: tauri::State<'_, ProcessMap>,
-) -> Result<String, String> {
-    let mut processes = state.lock().await;
+/// Waits for the stdout and stderr captures; whichever hits the output limit
+/// first ends both. Returns whether the output was truncated.
+async fn capture_both(
+    stdout: impl std::future::Future<Output = Result<bool, String>>,
+    stderr: impl std::future::Future<Output = Result<bool, String>>,
+) -> Result<bool, String> {
+    let stdout = std::pin::pin!(stdout);
+    let stderr = std::pin::pin!(stderr);
 
-    let process = processes
-        .get_mut(&connection_id)
-        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+    match futures_util::future::select(stdout, stderr).await {
+        Either::Left((Ok(false), other)) => other.await,
+        Either::Right((Ok(false), other)) => other.await,
+        Either::Left((result, _)) | Either::Right((result, _)) => result,
+    }
+}
+
+/// Writes `data` to a one-shot process's stdin and closes it. A process that
+/// exits without reading its input isn't an error.
+async fn feed_stdin(mut stdin: ChildStdin, data: Option<String>) -> Result<(), String> {
+    if let Some(data) = data {
+        match stdin.write_all(data.as_bytes()).await {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                return Err(format!("Failed to write to stdin: {}", e));
+            }
+            _ => {}
+        }
+    }
+    // Dropping stdin closes the pipe and signals EOF
+    drop(stdin);
+    Ok(())
+}
 
-    let stdout_reader = process
-        .stdout_reader
-        .as_mut()
-        .ok_or_else(|| "Process stdout not available".to_string())?;
+/// Feeds stdin while output is captured, then waits for the process. Once
+/// the output limit is hit the process is killed right away and the stdin
+/// write abandoned; a process that has stopped reading would otherwise keep
+/// that write pending. Returns the exit status and whether output was
+/// truncated.
+async fn run_to_completion(
+    child: &mut Child,
+    write_stdin: impl std::future::Future<Output = Result<(), String>>,
+    collect: impl std::future::Future<Output = Result<bool, String>>,
+) -> Result<(std::process::ExitStatus, bool), String> {
+    // Scoped so the stdin writer is dropped, closing the pipe, before waiting
+    let truncated = {
+        let write_stdin = std::pin::pin!(write_stdin);
+        let collect = std::pin::pin!(collect);
+        match futures_util::future::select(write_stdin, collect).await {
+            Either::Left((written, collect)) => {
+                written?;
+                collect.await?
+            }
+            Either::Right((Ok(true), _)) => {
+                let _ = child.start_kill();
+                true
+            }
+            Either::Right((collected, write_stdin)) => {
+                let truncated = collected?;
+                write_stdin.await?;
+                truncated
+            }
+        }
+    };
 
-    // Read one line from stdout
-    let mut line = String::new();
-    stdout_reader
-        .read_line(&mut line)
+    let status = child
+        .wait()
         .await
-        .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+        .map_err(|e| format!("Failed to wait for process: {}", e))?;
+    Ok((status, truncated))
+}
 
-    Ok(line)
+/// Decodes output chunks for `run-output` events. A multi-byte character
+/// split across two reads is held back until the rest of it arrives.
+#[derive(Default)]
+struct ChunkDecoder {
+    pending: Vec<u8>,
+}
+
+impl ChunkDecoder {
+    fn decode(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Only an incomplete sequence at the very end is worth waiting for
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+}
+
+fn emit_run_chunk(app: &tauri::AppHandle, event: &str, stream: &'static str, data: &str) {
+    if !data.is_empty() {
+        let _ = app.emit(event, RunChunk { stream, data });
+    }
 }
 
+/// Runs a command to completion, feeding it `stdin_data` and closing stdin,
+/// and returns everything it printed. The process is never added to the
+/// process map and is killed if it outlives `timeout_ms`, or once stdout and
+/// stderr together exceed `max_output_bytes`.
 #[tauri::command]
-pub async fn kill_process(
-    connection_id: String,
-    state: tauri::State<'_, ProcessMap>,
-) -> Result<(), String> {
-    let mut processes = state.lock().await;
+pub async fn spawn_and_run(
+    command: String,
+    args: Vec<String>,
+    stdin_data: Option<String>,
+    timeout_ms: u64,
+    max_output_bytes: Option<usize>,
+) -> Result<RunOutput, String> {
+    let mut child = Command::new(&command)
+        .args(&args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to spawn process '{}': {}", command, e))?;
 
-    let mut process = processes
-        .remove(&connection_id)
-        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to capture stdin".to_string())?;
 
-    process
-        .child
-        .kill()
-        .await
-        .map_err(|e| format!("Failed to kill process: {}", e))?;
+    // Write stdin concurrently with collecting output so a process that
+    // produces a lot of output before reading all input can't deadlock us.
+    let write_stdin = feed_stdin(stdin, stdin_data);
 
-    Ok(())
-}
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Failed to capture stdout".to_string())?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture stderr".to_string())?;
 
-#[tauri::command]
-pub async fn list_processes(state: tauri::State<'_, ProcessMap>) -> Result<Vec<ProcessInfo>, String> {
-    let processes = state.lock().await;
+    let mut stdout_bytes = Vec::new();
+    let mut stderr_bytes = Vec::new();
+    let used = AtomicUsize::new(0);
 
-    let info_list = processes
-        .values()
-        .map(|p| p.info.clone())
-        .collect();
+    let collect = capture_both(
+        capture_output(stdout, &mut stdout_bytes, &used, max_output_bytes, |_| {}),
+        capture_output(stderr, &mut stderr_bytes, &used, max_output_bytes, |_| {}),
+    );
 
-    Ok(info_list)
+    let run = run_to_completion(&mut child, write_stdin, collect);
+
+    let (status, truncated) = tokio::time::timeout(Duration::from_millis(timeout_ms), run)
+        .await
+        .map_err(|_| format!("Process '{}' timed out after {}ms", command, timeout_ms))??;
+
+    Ok(RunOutput {
+        stdout: String::from_utf8_lossy(&stdout_bytes).to_string(),
+        stderr: String::from_utf8_lossy(&stderr_bytes).to_string(),
+        exit_code: status.code(),
+        truncated,
+    })
 }
 
-#[tauri::command]
-pub async fn get_process_info(
-    connection_id: String,
-    state: tauri::State<'_, ProcessMap>,
-) -> Result<ProcessInfo, String> {
-    let processes = state.lock().await;
+async fn run_streamed(
+    app: &tauri::AppHandle,
+    command: &str,
+    args: &[String],
+    stdin_data: Option<String>,
+    max_output_bytes: Option<usize>,
+    request_id: &str,
+    flag: &CancelFlag,
+) -> Result<RunOutput, String> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to spawn process '{}': {}", command, e))?;
 
-    let process = processes
-        .get(&connection_id)
-        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to capture stdin".to_string())?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Failed to capture stdout".to_string())?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture stderr".to_string())?;
+
+    let event = format!("run-output://{}", request_id);
+    let mut stdout_bytes = Vec::new();
+    let mut stderr_bytes = Vec::new();
+    let used = AtomicUsize::new(0);
+    let mut stdout_decoder = ChunkDecoder::default();
+    let mut stderr_decoder = ChunkDecoder::default();
+
+    let run = async {
+        let collect = capture_both(
+            capture_output(stdout, &mut stdout_bytes, &used, max_output_bytes, |chunk| {
+                emit_run_chunk(app, &event, "stdout", &stdout_decoder.decode(chunk))
+            }),
+            capture_output(stderr, &mut stderr_bytes, &used, max_output_bytes, |chunk| {
+                emit_run_chunk(app, &event, "stderr", &stderr_decoder.decode(chunk))
+            }),
+        );
+        run_to_completion(&mut child, feed_stdin(stdin, stdin_data), collect).await
+    };
+
+    let outcome = flag.run(run).await;
+    let (status, truncated) = match outcome {
+        Some(status) => status?,
+        None => {
+            let _ = child.start_kill();
+            return Err(format!("Run of '{}' was cancelled", command));
+        }
+    };
+
+    Ok(RunOutput {
+        stdout: String::from_utf8_lossy(&stdout_bytes).to_string(),
+        stderr: String::from_utf8_lossy(&stderr_bytes).to_string(),
+        exit_code: status.code(),
+        truncated,
+    })
+}
+
+/// Like `spawn_and_run`, but emits stdout/stderr as `run-output://{request_id}`
+/// events as it's read, then returns the full output. `cancel_request` with
+/// the same `request_id` kills it, as does passing `max_output_bytes`.
+#[tauri::command]
+pub async fn spawn_and_run_streamed(
+    command: String,
+    args: Vec<String>,
+    stdin_data: Option<String>,
+    max_output_bytes: Option<usize>,
+    request_id: String,
+    app: tauri::AppHandle,
+    requests_state: tauri::State<'_, RequestMap>,
+) -> Result<RunOutput, String> {
+    let flag = requests::register(&requests_state, &request_id).await?;
+    let result = run_streamed(&app, &command, &args, stdin_data, max_output_bytes, &request_id, &flag).await;
+    requests::finish(&requests_state, &request_id).await;
+    result
+}
+
+#[tauri::command]
+pub async fn send_mcp_message(
+    connection_id: String,
+    message: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    send_message(state.inner(), &connection_id, &message).await
+}
+
+#[derive(Debug, Serialize)]
+pub struct BroadcastResult {
+    pub connection_id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Writes the same line to several processes' stdin concurrently, e.g. to
+/// compare how different agents answer one prompt. A failed write (broken
+/// pipe, unknown id, rate limit) is reported for that connection without
+/// affecting the others. Results are in the order of `connection_ids`, with
+/// duplicates sent to once.
+#[tauri::command]
+pub async fn broadcast_stdin(
+    connection_ids: Vec<String>,
+    data: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<BroadcastResult>, String> {
+    let mut seen = HashSet::new();
+    let connection_ids: Vec<String> = connection_ids
+        .into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+
+    let writes = connection_ids
+        .iter()
+        .map(|connection_id| send_message(state.inner(), connection_id, &data));
+    let outcomes = futures_util::future::join_all(writes).await;
+
+    Ok(connection_ids
+        .into_iter()
+        .zip(outcomes)
+        .map(|(connection_id, outcome)| {
+            BroadcastResult {
+                ok: outcome.is_ok(),
+                error: outcome.err(),
+                connection_id,
+            }
+        })
+        .collect())
+}
+
+/// Upper bound on how much output is buffered while waiting for a JSON value
+/// to become complete, so a server that never closes its object can't grow
+/// the buffer without limit.
+const MAX_JSON_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Reads the next line (or complete JSON message) from a process's stdout.
+/// The map is only locked to look the process up and to record the line;
+/// the wait itself holds just this process's reader lock.
+async fn read_response(
+    state: &ProcessMap,
+    connection_id: &str,
+    complete_json: bool,
+) -> Result<String, String> {
+    let connection = connection(state, connection_id).await?;
+
+    let line = {
+        let mut stdout_reader = connection.stdout()?.lock().await;
+        if complete_json {
+            read_complete_json(&mut stdout_reader).await?
+        } else {
+            // Read one line from stdout
+            let mut line = String::new();
+            stdout_reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+            line
+        }
+    };
+
+    handle_incoming(state, &connection, &line).await;
+    Ok(line)
+}
+
+#[tauri::command]
+pub async fn read_mcp_response(
+    connection_id: String,
+    complete_json: Option<bool>,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<String, String> {
+    read_response(state.inner(), &connection_id, complete_json.unwrap_or(false)).await
+}
+
+/// Reads lines until the buffered content parses as a complete JSON value.
+///
+/// Servers occasionally flush in the middle of a large object, so a single
+/// `read_line` can hand back a prefix that isn't valid JSON yet. Parse errors
+/// that only mean "more input needed" keep the loop going; real syntax errors
+/// are returned immediately.
+async fn read_complete_json(reader: &mut LineReader) -> Result<String, String> {
+    let mut buffer = String::new();
+
+    loop {
+        let mut line = String::new();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+
+        if bytes_read == 0 {
+            return if buffer.trim().is_empty() {
+                Err("Process stdout closed".to_string())
+            } else {
+                Err(format!(
+                    "Process stdout closed before JSON message was complete ({} bytes buffered)",
+                    buffer.len()
+                ))
+            };
+        }
+
+        // Skip blank keep-alive lines between messages
+        if buffer.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+
+        buffer.push_str(&line);
+
+        if buffer.len() > MAX_JSON_MESSAGE_BYTES {
+            return Err(format!(
+                "JSON message exceeded {} bytes without completing",
+                MAX_JSON_MESSAGE_BYTES
+            ));
+        }
+
+        match serde_json::from_str::<serde_json::Value>(&buffer) {
+            Ok(_) => return Ok(buffer.trim().to_string()),
+            Err(e) if e.is_eof() => continue,
+            Err(e) => return Err(format!("Invalid JSON from process: {}", e)),
+        }
+    }
+}
+
+/// Reads lines until a JSON-RPC message whose `id` equals `match_id` arrives,
+/// and returns it. Lines read along the way (notifications, other responses)
+/// are re-emitted as `mcp-message` events so they aren't lost.
+#[tauri::command]
+pub async fn wait_for_mcp_message(
+    connection_id: String,
+    match_id: serde_json::Value,
+    timeout_ms: u64,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<String, String> {
+    let connection = connection(state.inner(), &connection_id).await?;
+
+    let wait = async {
+        let mut reader = connection.stdout()?.lock().await;
+        let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+        read_until_id(state.inner(), &connection, &mut reader, &match_id, &mut forward).await
+    };
+
+    tokio::time::timeout(Duration::from_millis(timeout_ms), wait)
+        .await
+        .map_err(|_| {
+            format!(
+                "Timed out after {}ms waiting for message with id {}",
+                timeout_ms, match_id
+            )
+        })?
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessProtocol {
+    JsonRpc,
+    Ndjson,
+    PlainText,
+    /// Nothing was written back before the timeout.
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProtocolProbe {
+    pub protocol: ProcessProtocol,
+    /// The first line the process answered with.
+    pub response: Option<String>,
+}
+
+const PROTOCOL_PROBE_MESSAGE: &str = r#"{"jsonrpc":"2.0","id":"beaki-protocol-probe","method":"ping"}"#;
+
+fn classify_protocol(line: &str) -> ProcessProtocol {
+    match serde_json::from_str::<serde_json::Value>(line.trim()) {
+        Ok(value) if value.get("jsonrpc").and_then(|v| v.as_str()) == Some("2.0") => {
+            ProcessProtocol::JsonRpc
+        }
+        Ok(_) => ProcessProtocol::Ndjson,
+        Err(_) => ProcessProtocol::PlainText,
+    }
+}
+
+/// Sends a JSON-RPC `ping` (harmless to MCP servers) and guesses the
+/// process's framing from the first non-empty line it writes back. The
+/// response line is consumed, so probe before starting a real session.
+#[tauri::command]
+pub async fn probe_process_protocol(
+    connection_id: String,
+    timeout_ms: u64,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<ProtocolProbe, String> {
+    let connection = connection(state.inner(), &connection_id).await?;
+    let mut stdout_reader = connection.stdout()?.lock().await;
+
+    send_message(state.inner(), &connection_id, PROTOCOL_PROBE_MESSAGE).await?;
+
+    let read_first_line = async {
+        loop {
+            let mut line = String::new();
+            let bytes_read = stdout_reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+            if bytes_read == 0 {
+                return Ok(None);
+            }
+
+            handle_incoming(state.inner(), &connection, &line).await;
+            if !line.trim().is_empty() {
+                return Ok::<_, String>(Some(line));
+            }
+        }
+    };
+
+    let response = tokio::time::timeout(Duration::from_millis(timeout_ms), read_first_line)
+        .await
+        .unwrap_or(Ok(None))?;
+
+    Ok(ProtocolProbe {
+        protocol: response
+            .as_deref()
+            .map(classify_protocol)
+            .unwrap_or(ProcessProtocol::Unknown),
+        response,
+    })
+}
+
+/// Timeout for each ping while measuring latency.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends an MCP `ping` and waits for its response, returning the round trip.
+async fn ping_once(
+    connection_id: &str,
+    timeout: Duration,
+    app: &tauri::AppHandle,
+    state: &ProcessMap,
+) -> Result<Duration, String> {
+    let id = serde_json::Value::String(format!("beaki-ping-{}", uuid::Uuid::new_v4()));
+    let message = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": "ping" });
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(app, connection, line);
+
+    let started = Instant::now();
+    tokio::time::timeout(timeout, exchange(state, connection_id, &message.to_string(), &id, &mut forward))
+        .await
+        .map_err(|_| format!("Ping timed out after {}ms", timeout.as_millis()))??;
+
+    Ok(started.elapsed())
+}
+
+/// Pings an MCP server and returns the round-trip time in microseconds.
+#[tauri::command]
+pub async fn ping_mcp_server(
+    connection_id: String,
+    timeout_ms: u64,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<u64, String> {
+    let elapsed = ping_once(&connection_id, Duration::from_millis(timeout_ms), &app, state.inner()).await?;
+    Ok(elapsed.as_micros() as u64)
+}
+
+/// Pings an MCP server `samples` times, `interval_ms` apart, and returns each
+/// round-trip time in microseconds.
+#[tauri::command]
+pub async fn measure_mcp_latency(
+    connection_id: String,
+    samples: u32,
+    interval_ms: u64,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<u64>, String> {
+    let mut latencies = Vec::with_capacity(samples as usize);
+
+    for sample in 0..samples {
+        if sample > 0 {
+            tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+        }
+        let elapsed = ping_once(&connection_id, PING_TIMEOUT, &app, state.inner()).await?;
+        latencies.push(elapsed.as_micros() as u64);
+    }
+
+    Ok(latencies)
+}
+
+/// Calls an MCP tool and returns the `result` of the `tools/call` response.
+/// A JSON-RPC `error` response is returned as the error string. The server
+/// must already be initialized.
+#[tauri::command]
+pub async fn call_mcp_tool(
+    connection_id: String,
+    tool_name: String,
+    arguments: Option<serde_json::Value>,
+    timeout_ms: u64,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({
+        "name": tool_name,
+        "arguments": arguments.unwrap_or_else(|| serde_json::json!({})),
+    });
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    request(
+        state.inner(),
+        &connection_id,
+        "tools/call",
+        params,
+        Duration::from_millis(timeout_ms),
+        &mut forward,
+    )
+    .await
+    .map_err(|e| format!("Tool '{}': {}", tool_name, e))
+}
+
+/// Timeout for each request made while listing tools or resources.
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the server's tools, following `nextCursor` pages. The list is
+/// cached until the server sends `notifications/tools/list_changed`; pass
+/// `refresh` to bypass the cache.
+#[tauri::command]
+pub async fn list_mcp_tools(
+    connection_id: String,
+    refresh: Option<bool>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<McpTool>, String> {
+    if !refresh.unwrap_or(false) {
+        let processes = state.lock().await;
+        let process = processes
+            .get(&connection_id)
+            .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+        if let Some(tools) = &process.tools {
+            return Ok(tools.clone());
+        }
+    }
+
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    let tools: Vec<McpTool> = list_all(state.inner(), &connection_id, "tools/list", "tools", &mut forward).await?;
+    if let Some(process) = state.lock().await.get_mut(&connection_id) {
+        process.tools = Some(tools.clone());
+    }
+    Ok(tools)
+}
+
+/// Sends a paginated MCP list request, following `nextCursor` until the
+/// last page, and collects the `key` array of every page.
+async fn list_all<T: serde::de::DeserializeOwned>(
+    state: &ProcessMap,
+    connection_id: &str,
+    method: &str,
+    key: &str,
+    on_unmatched: &mut UnmatchedLine<'_>,
+) -> Result<Vec<T>, String> {
+    let mut items = Vec::new();
+    let mut cursor: Option<serde_json::Value> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => serde_json::json!({ "cursor": cursor }),
+            None => serde_json::json!({}),
+        };
+        let mut result = request(state, connection_id, method, params, LIST_TIMEOUT, on_unmatched).await?;
+
+        let page = result
+            .get_mut(key)
+            .map(serde_json::Value::take)
+            .unwrap_or_default();
+        let page: Vec<T> = serde_json::from_value(page)
+            .map_err(|e| format!("Invalid {} response: {}", method, e))?;
+        items.extend(page);
+
+        cursor = result.get("nextCursor").filter(|c| !c.is_null()).cloned();
+        if cursor.is_none() {
+            return Ok(items);
+        }
+    }
+}
+
+/// Returns the resources the server exposes, following `nextCursor` pages.
+#[tauri::command]
+pub async fn list_mcp_resources(
+    connection_id: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<McpResource>, String> {
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    list_all(state.inner(), &connection_id, "resources/list", "resources", &mut forward).await
+}
+
+/// Reads a resource by uri. Binary (`blob`) contents are base64-decoded.
+#[tauri::command]
+pub async fn read_mcp_resource(
+    connection_id: String,
+    uri: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<McpResourceContents>, String> {
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    let params = serde_json::json!({ "uri": uri });
+    let mut result = request(state.inner(), &connection_id, "resources/read", params, LIST_TIMEOUT, &mut forward).await?;
+    let contents = result
+        .get_mut("contents")
+        .map(serde_json::Value::take)
+        .unwrap_or_default();
+    let contents: Vec<RawResourceContents> = serde_json::from_value(contents)
+        .map_err(|e| format!("Invalid resources/read response: {}", e))?;
+
+    contents
+        .into_iter()
+        .map(|raw| -> Result<McpResourceContents, String> {
+            let blob = raw
+                .blob
+                .map(|blob| base64::engine::general_purpose::STANDARD.decode(blob))
+                .transpose()
+                .map_err(|e| format!("Invalid base64 in resource {}: {}", raw.uri, e))?;
+            Ok(McpResourceContents {
+                uri: raw.uri,
+                mime_type: raw.mime_type,
+                text: raw.text,
+                blob,
+            })
+        })
+        .collect()
+}
+
+/// Subscribes to updates of a resource. Each `notifications/resources/updated`
+/// for `uri` is then emitted as `mcp-resource-updated`.
+#[tauri::command]
+pub async fn subscribe_mcp_resource(
+    connection_id: String,
+    uri: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let subscriptions = connection(state.inner(), &connection_id).await?.subscriptions;
+
+    // Registered first so an update racing the response isn't dropped
+    subscriptions.lock().insert(uri.clone());
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    let params = serde_json::json!({ "uri": uri });
+    let result = request(state.inner(), &connection_id, "resources/subscribe", params, LIST_TIMEOUT, &mut forward).await;
+    if result.is_err() {
+        subscriptions.lock().remove(&uri);
+    }
+    result.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn unsubscribe_mcp_resource(
+    connection_id: String,
+    uri: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let subscriptions = connection(state.inner(), &connection_id).await?.subscriptions;
+
+    subscriptions.lock().remove(&uri);
+    let mut forward = |connection: &Connection, line: &str| forward_to_frontend(&app, connection, line);
+    let params = serde_json::json!({ "uri": uri });
+    request(state.inner(), &connection_id, "resources/unsubscribe", params, LIST_TIMEOUT, &mut forward)
+        .await
+        .map(|_| ())
+}
+
+/// Answers a `sampling/createMessage` request received as an
+/// `mcp-sampling-request` event. Pass the `CreateMessageResult` as `result`,
+/// or `error` to decline the request.
+#[tauri::command]
+pub async fn respond_mcp_sampling(
+    connection_id: String,
+    request_id: serde_json::Value,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let response = match (result, error) {
+        (_, Some(message)) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "error": { "code": -1, "message": message },
+        }),
+        (Some(result), None) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "result": result,
+        }),
+        (None, None) => return Err("Either result or error is required".to_string()),
+    };
+
+    send_message(state.inner(), &connection_id, &response.to_string()).await
+}
+
+/// Zeroes a connection's `bytes_sent` and `bytes_received` counters.
+#[tauri::command]
+pub async fn reset_process_counters(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let processes = state.lock().await;
+
+    let process = processes
+        .get(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    process.counters.reset();
+    Ok(())
+}
+
+/// Marks a connection as active, postponing its idle timeout.
+#[tauri::command]
+pub async fn reset_idle_timer(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let processes = state.lock().await;
+
+    let process = processes
+        .get(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    process.activity.touch();
+    Ok(())
+}
+
+/// Starts the child as the leader of a new process group, so everything it
+/// launches (e.g. the node process behind `npx`) can be killed with it.
+fn in_new_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// Kills every process in the group led by `pid`. Best effort: the group may
+/// already be gone.
+async fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    {
+        // SAFETY: killpg only reads its integer arguments
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await;
+    }
+}
+
+/// Kills a process along with its process group, treating a child that has
+/// already exited as success.
+async fn terminate(process: &mut ManagedProcess) -> Result<(), String> {
+    // Once reaped, the leader's pid (and so the group id) can be reused by an
+    // unrelated process; signalling it then could kill someone else's
+    if process.exit_code.is_some() {
+        process.end_session().await;
+        return Ok(());
+    }
+
+    // Until it's reaped, even an exited leader holds its pid, so the group
+    // can be signalled safely. Descendants can outlive the leader, so do it
+    // before reaping.
+    if let Some(pid) = process.pid {
+        kill_process_group(pid).await;
+    }
+
+    if let Ok(Some(status)) = process.child.try_wait() {
+        process.exit_code.get_or_insert(status.code());
+        process.end_session().await;
+        return Ok(());
+    }
+
+    match process.child.kill().await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => {}
+        Err(e) => return Err(format!("Failed to kill process: {}", e)),
+    }
+    process.exit_code.get_or_insert(None);
+    process.end_session().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn kill_process(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let mut processes = state.lock().await;
+
+    let mut process = processes
+        .remove(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    terminate(&mut process).await
+}
+
+/// Kills every process of the given type (`mcp` or `cli`) and returns the
+/// connection ids removed. Processes that refuse to die stay in the map.
+#[tauri::command]
+pub async fn kill_processes_by_type(
+    process_type: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<String>, String> {
+    let mut processes = state.lock().await;
+
+    let matching: Vec<String> = processes
+        .iter()
+        .filter(|(_, p)| p.info.process_type == process_type)
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    let mut killed = Vec::with_capacity(matching.len());
+    for id in matching {
+        let Some(process) = processes.get_mut(&id) else {
+            continue;
+        };
+        if terminate(process).await.is_ok() {
+            processes.remove(&id);
+            killed.push(id);
+        }
+    }
+
+    Ok(killed)
+}
+
+/// How long `wait_for_exit` waits for a process whose stdout has closed.
+const EXIT_WAIT: Duration = Duration::from_secs(2);
+
+/// Waits briefly for a process to exit once its stdout has closed and
+/// records the exit code as the reaper would. `None` when the entry is gone
+/// (killed), the process was killed by a signal, or it's still running with
+/// stdout closed.
+pub async fn wait_for_exit(processes: &ProcessMap, connection_id: &str) -> Option<i32> {
+    let deadline = Instant::now() + EXIT_WAIT;
+    loop {
+        {
+            let mut processes = processes.lock().await;
+            let process = processes.get_mut(connection_id)?;
+            if let Some(exit_code) = process.exit_code {
+                return exit_code;
+            }
+            if let Ok(Some(status)) = process.child.try_wait() {
+                process.exit_code = Some(status.code());
+                process.end_session().await;
+                return status.code();
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// How often the background reaper collects exited children.
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reaps every exited child still in the map and returns the connection ids
+/// reaped by this call. Entries are kept so their remaining output can still
+/// be read; `prune_dead_processes` removes them.
+pub async fn reap_exited(processes: &ProcessMap) -> Vec<String> {
+    let mut processes = processes.lock().await;
+
+    let mut reaped = Vec::new();
+    for (id, process) in processes.iter_mut() {
+        if process.exit_code.is_some() {
+            continue;
+        }
+        if let Ok(Some(status)) = process.child.try_wait() {
+            process.exit_code = Some(status.code());
+            process.end_session().await;
+            reaped.push(id.clone());
+        }
+    }
+
+    reaped
+}
+
+/// Periodically reaps exited children so none linger as zombies while their
+/// entries wait to be pruned or killed.
+pub fn start_reaper(processes: ProcessMap) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(REAP_INTERVAL).await;
+            reap_exited(&processes).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn reap_zombies(state: tauri::State<'_, ProcessMap>) -> Result<Vec<String>, String> {
+    Ok(reap_exited(state.inner()).await)
+}
+
+/// Removes every process that has already exited and returns their connection
+/// ids. Dropping the entry closes its stdin/stdout pipes.
+#[tauri::command]
+pub async fn prune_dead_processes(
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<Vec<String>, String> {
+    let mut processes = state.lock().await;
+
+    let dead: Vec<String> = processes
+        .iter_mut()
+        .filter_map(|(id, p)| match p.child.try_wait() {
+            Ok(Some(status)) => {
+                p.exit_code.get_or_insert(status.code());
+                Some(id.clone())
+            }
+            Err(_) => Some(id.clone()),
+            Ok(None) => None,
+        })
+        .collect();
+
+    for id in &dead {
+        if let Some(process) = processes.remove(id) {
+            process.end_session().await;
+        }
+    }
+
+    Ok(dead)
+}
+
+#[tauri::command]
+pub async fn list_processes(state: tauri::State<'_, ProcessMap>) -> Result<Vec<ProcessInfo>, String> {
+    let processes = state.lock().await;
+
+    let info_list = processes
+        .values()
+        .map(|p| p.info_snapshot())
+        .collect();
+
+    Ok(info_list)
+}
+
+#[tauri::command]
+pub async fn get_process_info(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<ProcessInfo, String> {
+    let processes = state.lock().await;
+
+    let process = processes
+        .get(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    Ok(process.info_snapshot())
+}
+
+/// How often `wait_until_ready` checks whether the process has exited.
+const READY_EXIT_POLL: Duration = Duration::from_millis(250);
+
+/// Waits until the process prints a line matching its `ready_pattern`.
+/// Returns `true` once ready (immediately if it already is) and `false`
+/// after `timeout_ms` or once the process exits. Stdout lines are only seen
+/// when something reads them: the output stream or a request/response
+/// command.
+#[tauri::command]
+pub async fn wait_until_ready(
+    connection_id: String,
+    timeout_ms: u64,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<bool, String> {
+    let ready = {
+        let processes = state.lock().await;
+        let process = processes
+            .get(&connection_id)
+            .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+        process
+            .ready
+            .clone()
+            .ok_or_else(|| format!("Process '{}' was spawned without a ready_pattern", connection_id))?
+    };
+
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    loop {
+        let slice = READY_EXIT_POLL.min(deadline.saturating_duration_since(Instant::now()));
+        if let Some(ready) = ready.wait(slice).await {
+            return Ok(ready);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+
+        // Don't leave an exit to the reaper, which may not run for a while
+        let mut processes = state.lock().await;
+        let Some(process) = processes.get_mut(&connection_id) else {
+            return Ok(ready.is_ready());
+        };
+        if process.exit_code.is_none() {
+            if let Ok(Some(status)) = process.child.try_wait() {
+                process.exit_code = Some(status.code());
+                process.end_session().await;
+            }
+        }
+    }
+}
+
+/// Bytes written to a child's stdout pipe that haven't been read yet.
+#[cfg(unix)]
+fn pipe_bytes_available(stdout: &ChildStdout) -> std::io::Result<usize> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut available: libc::c_int = 0;
+    // SAFETY: FIONREAD writes one c_int through the pointer, which is valid
+    let result = unsafe { libc::ioctl(stdout.as_raw_fd(), libc::FIONREAD, &mut available) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available.max(0) as usize)
+}
+
+/// Bytes written to a child's stdout pipe that haven't been read yet.
+#[cfg(windows)]
+fn pipe_bytes_available(stdout: &ChildStdout) -> std::io::Result<usize> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Pipes::PeekNamedPipe;
+
+    let mut available: u32 = 0;
+    // SAFETY: the handle stays open while `stdout` is borrowed, and only
+    // the byte count is written
+    let ok = unsafe {
+        PeekNamedPipe(
+            stdout.as_raw_handle() as _,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            &mut available,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available as usize)
+}
+
+/// Whether a process has stdout the UI hasn't received yet: lines held back
+/// while streaming is paused or rate-limited, or, when not streaming, data
+/// sitting in the read buffer or the pipe. Lets the UI warn before
+/// dismissing an agent with new output.
+#[tauri::command]
+pub async fn has_pending_output(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<bool, String> {
+    let processes = state.lock().await;
+    let process = processes
+        .get(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    if let Some(output) = &process.output {
+        let stats = output.buffer_stats();
+        return Ok(stats.paused_lines > 0 || stats.pending_batch_lines > 0);
+    }
+
+    let Some(reader) = &process.stdout_reader else {
+        return Ok(false);
+    };
+    // Locked means a read is waiting, which takes data as soon as it arrives
+    let Ok(reader) = reader.try_lock() else {
+        return Ok(false);
+    };
+    if reader.has_buffered() {
+        return Ok(true);
+    }
+    pipe_bytes_available(reader.get_ref())
+        .map(|available| available > 0)
+        .map_err(|e| format!("Failed to check pending output: {}", e))
+}
+
+/// Snapshot of every managed process for bug reports: info, status, pid,
+/// uptime, byte counters and output buffer sizes. Secret-looking arguments
+/// are redacted and environment variables are never included.
+#[tauri::command]
+pub async fn dump_process_state(
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<serde_json::Value, String> {
+    // Pick up exits the periodic reaper hasn't seen yet
+    reap_exited(state.inner()).await;
+
+    let processes = state.lock().await;
+
+    let mut dump = serde_json::Map::new();
+    for (id, process) in processes.iter() {
+        let mut info = process.info_snapshot();
+        info.args = crate::diagnostics::redact_args(&info.args);
+
+        let mut entry = serde_json::json!({
+            "info": info,
+            "status": if process.exit_code.is_some() { "exited" } else { "running" },
+            "exit_code": process.exit_code.flatten(),
+            "pid": process.pid,
+            "uptime_ms": process.started_at.elapsed().as_millis() as u64,
+            "idle_ms": process.activity.idle_for().as_millis() as u64,
+            "initialized": process.capabilities.is_some(),
+            "cached_tools": process.tools.as_ref().map(Vec::len),
+            "resource_subscriptions": process.subscriptions.lock().len(),
+            "output": process.output.as_ref().map(|output| output.buffer_stats()),
+        });
+        crate::diagnostics::redact_secrets(&mut entry);
+        dump.insert(id.clone(), entry);
+    }
+
+    Ok(serde_json::Value::Object(dump))
+}
+
+/// Returns the capabilities each MCP server reported during its `initialize`
+/// handshake, keyed by connection id. Servers that haven't completed the
+/// handshake map to `null`.
+#[tauri::command]
+pub async fn get_all_mcp_capabilities(
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<HashMap<String, serde_json::Value>, String> {
+    let processes = state.lock().await;
+
+    let capabilities = processes
+        .iter()
+        .filter(|(_, p)| p.info.process_type == "mcp")
+        .map(|(id, p)| {
+            (
+                id.clone(),
+                p.capabilities.clone().unwrap_or(serde_json::Value::Null),
+            )
+        })
+        .collect();
+
+    Ok(capabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn spawn_cat(connection_id: &str) -> ManagedProcess {
+        spawn_stub(connection_id, "cat", &[])
+    }
+
+    #[cfg(unix)]
+    fn spawn_stub(connection_id: &str, command: &str, args: &[&str]) -> ManagedProcess {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("spawn cat");
+
+        ManagedProcess {
+            info: ProcessInfo {
+                connection_id: connection_id.to_string(),
+                process_type: "mcp".to_string(),
+                command: command.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+                log_file: None,
+                stdout_file: None,
+                stderr_file: None,
+                bytes_sent: 0,
+                bytes_received: 0,
+                rate_limit: None,
+                startup_ms: None,
+                ready: None,
+            },
+            pid: child.id(),
+            stdin: child.stdin.take().map(|stdin| Arc::new(Mutex::new(stdin))),
+            stdout_reader: child.stdout.take().map(|stdout| Arc::new(Mutex::new(LineReader::new(stdout)))),
+            stderr_target: StderrTarget::default(),
+            child,
+            capabilities: None,
+            pending_initialize_id: None,
+            tools: None,
+            subscriptions: ResourceSubscriptions::default(),
+            output: None,
+            activity: Activity::new(),
+            counters: ByteCounters::default(),
+            options: SpawnOptions::default(),
+            env: HashMap::new(),
+            log: None,
+            exit_code: None,
+            rate_limiter: None,
+            started_at: Instant::now(),
+            first_output_after: None,
+            initialized_after: None,
+            ready: None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn blocked_read_does_not_hold_process_map() {
+        tauri::async_runtime::block_on(async {
+            let state = create_process_map();
+            state.lock().await.insert("idle".to_string(), spawn_cat("idle"));
+            state.lock().await.insert("busy".to_string(), spawn_cat("busy"));
+
+            // Never answered: `idle` gets no input
+            let idle_read = {
+                let state = state.clone();
+                tauri::async_runtime::spawn(async move { read_response(&state, "idle", false).await })
+            };
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            let echoed = tokio::time::timeout(Duration::from_secs(5), async {
+                send_message(&state, "busy", "hello").await?;
+                read_response(&state, "busy", false).await
+            })
+            .await
+            .expect("read on another connection blocked behind the idle one");
+
+            assert_eq!(echoed.unwrap(), "hello\n");
+            assert!(!idle_read.inner().is_finished());
+            idle_read.abort();
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pending_request_does_not_hold_process_map() {
+        tauri::async_runtime::block_on(async {
+            let state = create_process_map();
+            let silent = spawn_stub("silent", "sh", &["-c", "cat >/dev/null"]);
+            state.lock().await.insert("silent".to_string(), silent);
+            state.lock().await.insert("echo".to_string(), spawn_cat("echo"));
+
+            // Never answered: `silent` swallows its input
+            let stuck = {
+                let state = state.clone();
+                tauri::async_runtime::spawn(async move {
+                    let id = serde_json::json!("stuck");
+                    let message = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": "ping" });
+                    exchange(&state, "silent", &message.to_string(), &id, &mut |_, _| {}).await
+                })
+            };
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            let echoed = tokio::time::timeout(Duration::from_secs(5), async {
+                let id = serde_json::json!("echoed");
+                let message = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": "ping" });
+                exchange(&state, "echo", &message.to_string(), &id, &mut |_, _| {}).await
+            })
+            .await
+            .expect("request on another connection blocked behind the silent one");
+            assert!(echoed.unwrap().contains("\"echoed\""));
+
+            let processes = tokio::time::timeout(Duration::from_secs(1), state.lock())
+                .await
+                .expect("process map still locked by the pending request");
+            assert_eq!(processes.len(), 2);
+            drop(processes);
+
+            assert!(!stuck.inner().is_finished());
+            stuck.abort();
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sampling_reply_is_accepted_during_tool_call() {
+        // Asks for a completion mid-call and only answers the call once the
+        // sampling response comes back on stdin
+        const SERVER: &str = r#"read call
+id=$(printf '%s' "$call" | sed -n 's/.*"id":"\([^"]*\)".*/\1/p')
+echo '{"jsonrpc":"2.0","id":"s1","method":"sampling/createMessage","params":{}}'
+read reply
+printf '{"jsonrpc":"2.0","id":"%s","result":{"content":[]}}\n' "$id"
+cat >/dev/null"#;
+
+        tauri::async_runtime::block_on(async {
+            let state = create_process_map();
+            let sampler = spawn_stub("sampler", "sh", &["-c", SERVER]);
+            state.lock().await.insert("sampler".to_string(), sampler);
+
+            let mut reply_to_sampling = {
+                let state = state.clone();
+                move |_: &Connection, line: &str| {
+                    if !line.contains("sampling/createMessage") {
+                        return;
+                    }
+                    let state = state.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let reply = r#"{"jsonrpc":"2.0","id":"s1","result":{"role":"assistant"}}"#;
+                        send_message(&state, "sampler", reply).await
+                    });
+                }
+            };
+            let params = serde_json::json!({ "name": "summarize", "arguments": {} });
+            let result = request(
+                &state,
+                "sampler",
+                "tools/call",
+                params,
+                Duration::from_secs(5),
+                &mut reply_to_sampling,
+            )
+            .await;
+
+            assert_eq!(result.unwrap(), serde_json::json!({ "content": [] }));
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timed_out_read_keeps_partial_line() {
+        const SERVER: &str = r#"printf '{"jsonrpc":"2.0",'; sleep 1; printf '"id":"x","result":{}}\n'; cat >/dev/null"#;
+
+        tauri::async_runtime::block_on(async {
+            let state = create_process_map();
+            state.lock().await.insert("slow".to_string(), spawn_stub("slow", "sh", &["-c", SERVER]));
+
+            let early = tokio::time::timeout(Duration::from_millis(200), read_response(&state, "slow", false)).await;
+            assert!(early.is_err(), "read should time out mid-line");
+
+            let line = tokio::time::timeout(Duration::from_secs(3), read_response(&state, "slow", false))
+                .await
+                .expect("rest of the line never arrived")
+                .unwrap();
+            let message: serde_json::Value = serde_json::from_str(line.trim()).expect("line was cut");
+            assert_eq!(message["id"], "x");
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_limit_kills_process_that_ignores_stdin() {
+        // More input than a pipe holds, to a process that never reads it
+        let input = "x".repeat(1024 * 1024);
+
+        tauri::async_runtime::block_on(async {
+            let started = Instant::now();
+            let output = spawn_and_run("yes".to_string(), Vec::new(), Some(input), 10_000, Some(4096))
+                .await
+                .expect("run should end at the output limit, not the timeout");
+            assert!(output.truncated);
+            assert_eq!(output.stdout.len(), 4096);
+            assert!(started.elapsed() < Duration::from_secs(5));
+        });
+    }
+
+    #[test]
+    fn delayed_rate_limit_reserves_future_slots() {
+        let mut limiter = RateLimiter::new(2, RateLimitMode::Delay);
+        assert_eq!(limiter.reserve(), Ok(None));
+        assert_eq!(limiter.reserve(), Ok(None));
+
+        let first = limiter.reserve().unwrap().expect("bucket is empty");
+        let second = limiter.reserve().unwrap().expect("bucket is empty");
+        assert!(second > first);
+        assert!(second - first >= Duration::from_millis(450));
+        assert_eq!(limiter.state().available, 0.0);
+    }
+
+    #[test]
+    fn reject_rate_limit_counts_rejections() {
+        let mut limiter = RateLimiter::new(1, RateLimitMode::Reject);
+        assert_eq!(limiter.reserve(), Ok(None));
+        assert!(limiter.reserve().is_err());
+        assert_eq!(limiter.state().rejected, 1);
+    }
+
+    #[test]
+    fn shell_syntax_ignores_pipes_inside_args() {
+        let args: Vec<String> = ["--match=a|b", ">", "build && run", "https://x/?a>b", "|"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(shell_syntax_args(&args), vec![">", "build && run", "|"]);
+    }
+
+    #[test]
+    fn cmd_quoting_escapes_metacharacters() {
+        assert_eq!(quote_cmd_arg("foo&calc"), "^\"foo^&calc^\"");
+        assert_eq!(quote_cmd_arg("say \"hi\""), "^\"say ^\"^\"hi^\"^\"^\"");
+        assert_eq!(quote_cmd_arg("%PATH%"), "^\"^%PATH^%^\"");
+        assert_eq!(quote_cmd_arg(""), "^\"^\"");
+    }
 
-    Ok(process.info.clone())
+    #[cfg(unix)]
+    #[test]
+    fn shell_command_quotes_plain_args() {
+        let (_, args) = shell_command(
+            "node".to_string(),
+            vec!["my server.js".to_string(), "&&".to_string(), "echo".to_string()],
+        );
+        assert_eq!(args, vec!["-c", "node 'my server.js' && echo"]);
+    }
 }
@@ -0,0 +1,718 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStderr, ChildStdout};
+
+use crate::process_manager::{
+    route_server_message, wait_for_exit, Activity, ByteCounters, ProcessMap, ResourceSubscriptions,
+    StdoutReader,
+};
+
+/// Maximum number of lines held while output emission is paused or waiting
+/// for a batch flush. Once full, the oldest lines are dropped.
+pub const OUTPUT_BUFFER_CAPACITY: usize = 1000;
+
+/// How often coalesced lines are flushed when a process exceeds its emit rate.
+const BATCH_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+struct LineBatch {
+    lines: Vec<String>,
+}
+
+/// Output delivered through a caller-supplied channel instead of global events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessOutputChunk {
+    Line { line: String },
+    Batch { lines: Vec<String> },
+    Stderr { line: String },
+    /// Sent once stdout reaches end of file, with the exit code if the
+    /// process has exited by then (`None` when killed).
+    Closed { exit_code: Option<i32> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ClosedPayload {
+    exit_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReadyPayload<'a> {
+    connection_id: &'a str,
+    /// The output line that matched `ready_pattern`.
+    line: &'a str,
+}
+
+/// Watches a process's output for its `ready_pattern`. Shared by every
+/// reader of stdout and stderr, since servers print their ready line on
+/// either.
+pub struct ReadySignal {
+    app: AppHandle,
+    connection_id: String,
+    pattern: regex::Regex,
+    /// `None` while waiting, then `Some(true)` once ready or `Some(false)`
+    /// if the process exited first.
+    state: tokio::sync::watch::Sender<Option<bool>>,
+}
+
+impl ReadySignal {
+    pub fn new(app: AppHandle, connection_id: String, pattern: regex::Regex) -> Self {
+        ReadySignal {
+            app,
+            connection_id,
+            pattern,
+            state: tokio::sync::watch::channel(None).0,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.state.borrow() == Some(true)
+    }
+
+    /// Marks the process ready and emits `process-ready` on the first line
+    /// matching the pattern.
+    pub fn check_line(&self, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if self.state.borrow().is_some() || !self.pattern.is_match(line) {
+            return;
+        }
+        // Only the reader that settles the state emits, should two match at once
+        if self.settle(true) {
+            let _ = self.app.emit(
+                "process-ready",
+                ReadyPayload {
+                    connection_id: &self.connection_id,
+                    line,
+                },
+            );
+        }
+    }
+
+    /// Ends the wait for a process that exited without becoming ready.
+    pub fn mark_exited(&self) {
+        self.settle(false);
+    }
+
+    fn settle(&self, ready: bool) -> bool {
+        self.state.send_if_modified(|state| match state {
+            Some(_) => false,
+            None => {
+                *state = Some(ready);
+                true
+            }
+        })
+    }
+
+    /// Waits up to `timeout` for the process to become ready or exit, and
+    /// returns which, or `None` if it's still waiting.
+    pub async fn wait(&self, timeout: Duration) -> Option<bool> {
+        let mut state = self.state.subscribe();
+        match tokio::time::timeout(timeout, state.wait_for(Option::is_some)).await {
+            Ok(Ok(state)) => *state,
+            _ => None,
+        }
+    }
+}
+
+/// Where a process's stderr lines go once `stream_process_output` starts.
+pub type StderrTarget = Arc<std::sync::OnceLock<Arc<OutputStream>>>;
+
+/// Append-only log of a process's output, shared by every reader.
+pub struct ProcessLog {
+    file: tokio::sync::Mutex<tokio::fs::File>,
+}
+
+impl ProcessLog {
+    pub async fn open(path: &str) -> Result<Self, String> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| format!("Failed to open log file '{}': {}", path, e))?;
+
+        Ok(Self {
+            file: tokio::sync::Mutex::new(file),
+        })
+    }
+
+    /// Appends a line tagged with its stream. Logging failures are ignored so
+    /// a full disk never interrupts the session itself.
+    pub async fn write_line(&self, stream: &str, line: &str) {
+        let entry = format!("[{}] {}\n", stream, line.trim_end_matches(['\r', '\n']));
+        let mut file = self.file.lock().await;
+        let _ = file.write_all(entry.as_bytes()).await;
+    }
+}
+
+/// Reads one line like `read_line`, but replaces invalid UTF-8 with U+FFFD
+/// instead of failing, so one stray byte from a process emitting binary or
+/// Latin-1 output doesn't end the session.
+pub async fn read_line_lossy<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    line: &mut String,
+) -> std::io::Result<usize> {
+    let mut bytes = Vec::new();
+    let read = reader.read_until(b'\n', &mut bytes).await?;
+    line.push_str(&String::from_utf8_lossy(&bytes));
+    Ok(read)
+}
+
+/// A process's stdout with the part of a line read so far. Keeping the
+/// partial line here rather than in a local means a read cancelled by a
+/// timeout leaves it for the next read instead of dropping it and picking up
+/// mid-line.
+pub struct LineReader {
+    inner: BufReader<ChildStdout>,
+    partial: Vec<u8>,
+}
+
+impl LineReader {
+    pub fn new(stdout: ChildStdout) -> Self {
+        Self {
+            inner: BufReader::new(stdout),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Like `read_line_lossy`, but safe to cancel: returns the length of the
+    /// whole line, including any part buffered by an earlier cancelled read,
+    /// and 0 only at end of stream.
+    pub async fn read_line(&mut self, line: &mut String) -> std::io::Result<usize> {
+        self.inner.read_until(b'\n', &mut self.partial).await?;
+        let read = self.partial.len();
+        line.push_str(&String::from_utf8_lossy(&self.partial));
+        self.partial.clear();
+        Ok(read)
+    }
+
+    /// Whether output has been read from the pipe but not yet returned.
+    pub fn has_buffered(&self) -> bool {
+        !self.partial.is_empty() || !self.inner.buffer().is_empty()
+    }
+
+    pub fn get_ref(&self) -> &ChildStdout {
+        self.inner.get_ref()
+    }
+}
+
+/// Drains stderr for the life of the process so a chatty child never blocks
+/// on a full pipe. Lines go to the process log and, once streaming has
+/// started, out as stderr events; before that they're only logged.
+pub async fn pump_stderr(
+    app: AppHandle,
+    log: Option<Arc<ProcessLog>>,
+    target: StderrTarget,
+    ready: Option<Arc<ReadySignal>>,
+    mut reader: BufReader<ChildStderr>,
+) {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match read_line_lossy(&mut reader, &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if let Some(log) = &log {
+                    log.write_line("stderr", &line).await;
+                }
+                let line = line.trim_end_matches(['\r', '\n']);
+                if let Some(ready) = &ready {
+                    ready.check_line(line);
+                }
+                if let Some(output) = target.get().filter(|output| output.passes_filter(line)) {
+                    output.emit_stderr(&app, line.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Lines held back from emission, bounded by `OUTPUT_BUFFER_CAPACITY` and
+/// the process's `max_output_bytes` by dropping the oldest.
+#[derive(Default)]
+struct BoundedLines {
+    lines: VecDeque<String>,
+    bytes: usize,
+}
+
+impl BoundedLines {
+    fn push(&mut self, line: String, max_bytes: Option<usize>) {
+        self.bytes += line.len();
+        self.lines.push_back(line);
+
+        let max_bytes = max_bytes.unwrap_or(usize::MAX);
+        while self.lines.len() > OUTPUT_BUFFER_CAPACITY || self.bytes > max_bytes {
+            match self.lines.pop_front() {
+                Some(dropped) => self.bytes -= dropped.len(),
+                None => break,
+            }
+        }
+    }
+
+    fn take(&mut self) -> Vec<String> {
+        self.bytes = 0;
+        self.lines.drain(..).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+struct OutputState {
+    /// Lines read while paused, waiting to be emitted on resume.
+    paused: BoundedLines,
+    /// Lines over the emit rate, waiting for the next batch flush.
+    pending_batch: BoundedLines,
+    window_start: Instant,
+    emitted_in_window: u32,
+}
+
+/// Buffer sizes of a streaming connection, for diagnostics.
+#[derive(Debug, Serialize)]
+pub struct OutputBufferStats {
+    pub paused: bool,
+    pub paused_lines: usize,
+    pub paused_bytes: usize,
+    pub pending_batch_lines: usize,
+    pub channel_closed: bool,
+}
+
+/// State shared between a process entry and its background output reader.
+pub struct OutputStream {
+    connection_id: String,
+    paused: AtomicBool,
+    max_lines_per_sec: Option<u32>,
+    max_buffer_bytes: Option<usize>,
+    state: std::sync::Mutex<OutputState>,
+    channel: Option<Channel<ProcessOutputChunk>>,
+    /// Set once the channel's receiver has gone away.
+    channel_closed: AtomicBool,
+    /// Extra copy of every line for `start_output_websocket`.
+    mirror: std::sync::Mutex<Option<tokio::sync::broadcast::Sender<String>>>,
+    /// Only lines matching this are emitted; see `set_output_filter`.
+    filter: std::sync::Mutex<Option<regex::Regex>>,
+}
+
+impl OutputStream {
+    pub fn new(
+        connection_id: String,
+        max_lines_per_sec: Option<u32>,
+        max_buffer_bytes: Option<usize>,
+        filter: Option<regex::Regex>,
+        channel: Option<Channel<ProcessOutputChunk>>,
+    ) -> Self {
+        Self {
+            connection_id,
+            paused: AtomicBool::new(false),
+            max_lines_per_sec: max_lines_per_sec.filter(|rate| *rate > 0),
+            max_buffer_bytes,
+            state: std::sync::Mutex::new(OutputState {
+                paused: BoundedLines::default(),
+                pending_batch: BoundedLines::default(),
+                window_start: Instant::now(),
+                emitted_in_window: 0,
+            }),
+            channel,
+            channel_closed: AtomicBool::new(false),
+            mirror: std::sync::Mutex::new(None),
+            filter: std::sync::Mutex::new(filter),
+        }
+    }
+
+    pub fn set_filter(&self, filter: Option<regex::Regex>) {
+        *self.filter.lock().unwrap_or_else(|e| e.into_inner()) = filter;
+    }
+
+    /// Whether a line gets emitted under the current filter. Filtered lines
+    /// are still logged, counted and mirrored.
+    fn passes_filter(&self, line: &str) -> bool {
+        self.filter
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|filter| filter.is_match(line.trim_end_matches(['\r', '\n'])))
+            .unwrap_or(true)
+    }
+
+    /// Sets or clears the sender every line is copied to. Clearing it ends
+    /// the subscribers' streams.
+    pub fn set_mirror(&self, mirror: Option<tokio::sync::broadcast::Sender<String>>) {
+        *self.mirror.lock().unwrap_or_else(|e| e.into_inner()) = mirror;
+    }
+
+    pub fn subscribe_mirror(&self) -> Option<tokio::sync::broadcast::Receiver<String>> {
+        self.mirror
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|mirror| mirror.subscribe())
+    }
+
+    fn mirror_line(&self, line: &str) {
+        if let Some(mirror) = self.mirror.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            // No subscribers is fine; lines are only mirrored live
+            let _ = mirror.send(line.to_string());
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, OutputState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn stdout_event(&self) -> String {
+        format!("process-stdout://{}", self.connection_id)
+    }
+
+    pub fn stdout_batch_event(&self) -> String {
+        format!("process-stdout-batch://{}", self.connection_id)
+    }
+
+    pub fn stderr_event(&self) -> String {
+        format!("process-stderr://{}", self.connection_id)
+    }
+
+    pub fn closed_event(&self) -> String {
+        format!("process-closed://{}", self.connection_id)
+    }
+
+    fn is_channel_closed(&self) -> bool {
+        self.channel_closed.load(Ordering::SeqCst)
+    }
+
+    fn send_chunk(&self, chunk: ProcessOutputChunk) {
+        if let Some(channel) = &self.channel {
+            if channel.send(chunk).is_err() {
+                self.channel_closed.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn emit_line(&self, app: &AppHandle, line: String) {
+        match self.channel {
+            Some(_) => self.send_chunk(ProcessOutputChunk::Line { line }),
+            None => {
+                let _ = app.emit(&self.stdout_event(), line);
+            }
+        }
+    }
+
+    /// Stderr isn't paused or rate limited; it's rarely chatty and usually
+    /// what explains a failure.
+    fn emit_stderr(&self, app: &AppHandle, line: String) {
+        match self.channel {
+            Some(_) => self.send_chunk(ProcessOutputChunk::Stderr { line }),
+            None => {
+                let _ = app.emit(&self.stderr_event(), line);
+            }
+        }
+    }
+
+    fn emit_batch(&self, app: &AppHandle, lines: Vec<String>) {
+        match self.channel {
+            Some(_) => self.send_chunk(ProcessOutputChunk::Batch { lines }),
+            None => {
+                let _ = app.emit(&self.stdout_batch_event(), LineBatch { lines });
+            }
+        }
+    }
+
+    /// Emits a line, or buffers it while paused. Over the emit rate, lines
+    /// are queued for the next batch instead. The state lock is held
+    /// throughout so a concurrent resume or flush can't reorder lines.
+    fn deliver(&self, app: &AppHandle, line: String) {
+        let mut state = self.lock_state();
+
+        if self.paused.load(Ordering::SeqCst) {
+            state.paused.push(line, self.max_buffer_bytes);
+            return;
+        }
+
+        let Some(rate) = self.max_lines_per_sec else {
+            self.emit_line(app, line);
+            return;
+        };
+
+        // A new window goes out line by line again, after whatever the
+        // last one queued
+        if state.window_start.elapsed() >= Duration::from_secs(1) {
+            self.flush_locked(app, &mut state);
+            state.window_start = Instant::now();
+            state.emitted_in_window = 0;
+        }
+
+        if state.emitted_in_window < rate && state.pending_batch.is_empty() {
+            state.emitted_in_window += 1;
+            self.emit_line(app, line);
+        } else {
+            state.pending_batch.push(line, self.max_buffer_bytes);
+        }
+    }
+
+    /// Emits queued over-rate lines as a single `{ lines }` batch event.
+    fn flush_batch(&self, app: &AppHandle) {
+        let mut state = self.lock_state();
+        self.flush_locked(app, &mut state);
+    }
+
+    fn flush_locked(&self, app: &AppHandle, state: &mut OutputState) {
+        if !state.pending_batch.is_empty() {
+            self.emit_batch(app, state.pending_batch.take());
+        }
+    }
+
+    pub fn buffer_stats(&self) -> OutputBufferStats {
+        let state = self.lock_state();
+        OutputBufferStats {
+            paused: self.paused.load(Ordering::SeqCst),
+            paused_lines: state.paused.lines.len(),
+            paused_bytes: state.paused.bytes,
+            pending_batch_lines: state.pending_batch.lines.len(),
+            channel_closed: self.is_channel_closed(),
+        }
+    }
+
+    pub fn pause(&self) {
+        let _state = self.lock_state();
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Flushes lines buffered while paused, then resumes live emission. With
+    /// an emit rate configured, the backlog goes out as one batch.
+    pub fn resume(&self, app: &AppHandle) {
+        let mut state = self.lock_state();
+        let backlog = state.paused.take();
+
+        if self.max_lines_per_sec.is_some() {
+            for line in backlog {
+                state.pending_batch.push(line, self.max_buffer_bytes);
+            }
+            self.paused.store(false, Ordering::SeqCst);
+            self.flush_locked(app, &mut state);
+        } else {
+            for line in backlog {
+                self.emit_line(app, line);
+            }
+            self.paused.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// The parts of a process entry `pump_stdout` updates for each line, cloned
+/// out so the reader never holds the process map lock.
+struct PumpContext {
+    processes: ProcessMap,
+    activity: Activity,
+    counters: ByteCounters,
+    log: Option<Arc<ProcessLog>>,
+    subscriptions: ResourceSubscriptions,
+    ready: Option<Arc<ReadySignal>>,
+}
+
+async fn pump_stdout(app: AppHandle, output: Arc<OutputStream>, context: PumpContext, reader: StdoutReader) {
+    // Waits for a `read_mcp_response` already in progress, then keeps the
+    // reader for good
+    let mut reader = reader.lock_owned().await;
+
+    // A flush timeout can interrupt a read midway; the reader keeps the
+    // partial line for the next one.
+    let mut line = String::new();
+    let mut seen_output = false;
+    // Batches go out on a fixed schedule, not only when output goes quiet,
+    // so a process that never pauses still gets flushed
+    let mut next_flush = tokio::time::Instant::now() + BATCH_FLUSH_INTERVAL;
+
+    // A dropped channel means the caller is gone, so stop reading
+    while !output.is_channel_closed() {
+        let read = if output.max_lines_per_sec.is_some() {
+            if tokio::time::Instant::now() >= next_flush {
+                output.flush_batch(&app);
+                next_flush = tokio::time::Instant::now() + BATCH_FLUSH_INTERVAL;
+            }
+            match tokio::time::timeout_at(next_flush, reader.read_line(&mut line)).await {
+                Ok(read) => read,
+                Err(_) => continue,
+            }
+        } else {
+            reader.read_line(&mut line).await
+        };
+
+        match read {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                if !seen_output {
+                    seen_output = true;
+                    if let Some(process) = context.processes.lock().await.get_mut(&output.connection_id) {
+                        process.record_output_started();
+                    }
+                }
+                let line = std::mem::take(&mut line);
+                context.counters.add_received(read);
+                context.activity.touch();
+                if let Some(log) = &context.log {
+                    log.write_line("stdout", &line).await;
+                }
+                output.mirror_line(&line);
+                if let Some(ready) = &context.ready {
+                    ready.check_line(&line);
+                }
+                if route_server_message(&app, &output.connection_id, &context.subscriptions, &line) {
+                    continue;
+                }
+                if output.passes_filter(&line) {
+                    output.deliver(&app, line);
+                }
+            }
+        }
+    }
+
+    output.flush_batch(&app);
+    output.set_mirror(None);
+    if output.is_channel_closed() {
+        return;
+    }
+
+    let exit_code = wait_for_exit(&context.processes, &output.connection_id).await;
+    match output.channel {
+        Some(_) => output.send_chunk(ProcessOutputChunk::Closed { exit_code }),
+        None => {
+            let _ = app.emit(&output.closed_event(), ClosedPayload { exit_code });
+        }
+    }
+}
+
+/// Starts a background task that emits each stdout line as a
+/// `process-stdout://{connection_id}` event and each stderr line as a
+/// `process-stderr://{connection_id}` event, then `process-closed://
+/// {connection_id}` with the exit code once stdout ends. When `on_event` is
+/// given, output goes only to that channel as `ProcessOutputChunk`s instead,
+/// and streaming stops once the channel is dropped. Once streaming, the
+/// connection's stdout can no longer be read with `read_mcp_response`.
+#[tauri::command]
+pub async fn stream_process_output(
+    connection_id: String,
+    on_event: Option<Channel<ProcessOutputChunk>>,
+    app: AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let mut processes = state.lock().await;
+
+    let process = processes
+        .get_mut(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    if process.output.is_some() {
+        return Err(format!("Output is already streaming for '{}'", connection_id));
+    }
+    if let Some(path) = &process.info.stdout_file {
+        return Err(format!(
+            "Output of '{}' is redirected to {}; tail that file instead",
+            connection_id, path
+        ));
+    }
+
+    let reader = process
+        .stdout_reader
+        .take()
+        .ok_or_else(|| "Process stdout not available".to_string())?;
+
+    let output = Arc::new(OutputStream::new(
+        connection_id.clone(),
+        process.options.max_emit_rate,
+        process.options.max_output_bytes,
+        compile_filter(process.options.filter_pattern.as_deref())?,
+        on_event,
+    ));
+    process.output = Some(output.clone());
+    let _ = process.stderr_target.set(output.clone());
+
+    let context = PumpContext {
+        processes: state.inner().clone(),
+        activity: process.activity.clone(),
+        counters: process.counters.clone(),
+        log: process.log.clone(),
+        subscriptions: process.subscriptions.clone(),
+        ready: process.ready.clone(),
+    };
+    tauri::async_runtime::spawn(pump_stdout(app, output, context, reader));
+
+    Ok(())
+}
+
+/// Compiles an output filter; `None` or an empty pattern means no filter.
+pub fn compile_filter(pattern: Option<&str>) -> Result<Option<regex::Regex>, String> {
+    match pattern.filter(|p| !p.is_empty()) {
+        Some(pattern) => regex::Regex::new(pattern)
+            .map(Some)
+            .map_err(|e| format!("Invalid filter_pattern '{}': {}", pattern, e)),
+        None => Ok(None),
+    }
+}
+
+pub async fn output_stream(
+    connection_id: &str,
+    state: &tauri::State<'_, ProcessMap>,
+) -> Result<Arc<OutputStream>, String> {
+    let processes = state.lock().await;
+
+    let process = processes
+        .get(connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    process
+        .output
+        .clone()
+        .ok_or_else(|| format!("Output is not streaming for '{}'", connection_id))
+}
+
+/// Stops emitting output events while keeping the process and its reader
+/// alive. Lines produced meanwhile are buffered up to `OUTPUT_BUFFER_CAPACITY`
+/// lines and the process's `max_output_bytes`.
+#[tauri::command]
+pub async fn pause_process_output(
+    connection_id: String,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    output_stream(&connection_id, &state).await?.pause();
+    Ok(())
+}
+
+/// Emits any output buffered while paused and resumes live emission.
+#[tauri::command]
+pub async fn resume_process_output(
+    connection_id: String,
+    app: AppHandle,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    output_stream(&connection_id, &state).await?.resume(&app);
+    Ok(())
+}
+
+/// Replaces the output filter of a process: only stdout and stderr lines
+/// matching `pattern` are emitted from then on. `None` or an empty pattern
+/// emits everything again. Applies to a later `stream_process_output` too.
+#[tauri::command]
+pub async fn set_output_filter(
+    connection_id: String,
+    pattern: Option<String>,
+    state: tauri::State<'_, ProcessMap>,
+) -> Result<(), String> {
+    let filter = compile_filter(pattern.as_deref())?;
+
+    let mut processes = state.lock().await;
+    let process = processes
+        .get_mut(&connection_id)
+        .ok_or_else(|| format!("Process with ID '{}' not found", connection_id))?;
+
+    process.options.filter_pattern = pattern;
+    if let Some(output) = &process.output {
+        output.set_filter(filter);
+    }
+    Ok(())
+}
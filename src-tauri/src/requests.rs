@@ -0,0 +1,125 @@
+use futures_util::future::{self, Either};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Cancellation flag handed to a long-running operation.
+#[derive(Clone, Default)]
+pub struct CancelFlag(Arc<CancelState>);
+
+impl CancelFlag {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Resolves once the flag is cancelled.
+    pub async fn cancelled(&self) {
+        let mut notified = std::pin::pin!(self.0.notify.notified());
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Runs `work` until it completes or the flag is cancelled, in which case
+    /// `work` is dropped and `None` returned. Dropping a pending HTTP read
+    /// closes the connection, so the server stops too.
+    pub async fn run<F: Future>(&self, work: F) -> Option<F::Output> {
+        let work = std::pin::pin!(work);
+        let cancelled = std::pin::pin!(self.cancelled());
+        match future::select(work, cancelled).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+}
+
+/// Tracks in-flight streaming operations by the frontend-chosen request id so
+/// they can be cancelled with `cancel_request`.
+pub type RequestMap = Arc<Mutex<HashMap<String, CancelFlag>>>;
+
+pub fn create_request_map() -> RequestMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+pub async fn register(requests: &RequestMap, request_id: &str) -> Result<CancelFlag, String> {
+    let mut requests = requests.lock().await;
+    if requests.contains_key(request_id) {
+        return Err(format!("Request '{}' is already running", request_id));
+    }
+
+    let flag = CancelFlag::default();
+    requests.insert(request_id.to_string(), flag.clone());
+    Ok(flag)
+}
+
+pub async fn finish(requests: &RequestMap, request_id: &str) {
+    requests.lock().await.remove(request_id);
+}
+
+/// In-flight Ollama requests, keyed by an internal id, so every one can be
+/// cancelled on exit or with `cancel_all_ollama`. A request started with a
+/// `request_id` shares its flag with the `RequestMap` entry.
+pub type OllamaRequestMap = Arc<Mutex<HashMap<String, CancelFlag>>>;
+
+pub fn create_ollama_request_map() -> OllamaRequestMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Tracks `flag` until the returned key is passed to `untrack_ollama`.
+pub async fn track_ollama(ollama: &OllamaRequestMap, flag: &CancelFlag) -> String {
+    let key = uuid::Uuid::new_v4().to_string();
+    ollama.lock().await.insert(key.clone(), flag.clone());
+    key
+}
+
+pub async fn untrack_ollama(ollama: &OllamaRequestMap, key: &str) {
+    ollama.lock().await.remove(key);
+}
+
+/// Cancels every tracked Ollama request and returns how many there were.
+pub async fn cancel_ollama_requests(ollama: &OllamaRequestMap) -> usize {
+    let ollama = ollama.lock().await;
+    for flag in ollama.values() {
+        flag.cancel();
+    }
+    ollama.len()
+}
+
+/// Cancels every in-flight `ollama_generate`, `ollama_chat`, `ollama_pull`
+/// and `semantic_file_search`, dropping their connections. Returns how many
+/// were running.
+#[tauri::command]
+pub async fn cancel_all_ollama(ollama: tauri::State<'_, OllamaRequestMap>) -> Result<usize, String> {
+    Ok(cancel_ollama_requests(&ollama).await)
+}
+
+/// Cancels a running streaming request. Returns `false` if no request with
+/// that id is running (it may already have finished).
+#[tauri::command]
+pub async fn cancel_request(
+    request_id: String,
+    state: tauri::State<'_, RequestMap>,
+) -> Result<bool, String> {
+    match state.lock().await.get(&request_id) {
+        Some(flag) => {
+            flag.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
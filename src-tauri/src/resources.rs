@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+use crate::process_manager::ProcessMap;
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    /// Load per CPU: the 1-minute load average divided by the CPU count on
+    /// Unix, current CPU usage (0-1) on Windows, which has no load average.
+    pub cpu_load: f64,
+    pub cpu_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceCheck {
+    pub usage: ResourceUsage,
+    /// Why a spawn would be refused, when a threshold is crossed.
+    pub exhausted: Option<String>,
+}
+
+/// Combined usage of the managed processes and their descendants.
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetUsage {
+    /// Sum of per-process CPU usage, where 100 is one full core.
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    /// Managed processes still running.
+    pub process_count: usize,
+}
+
+/// Whether `pid` is one of `roots` or descends from one. Children count
+/// because wrappers such as `npx` or `sh -c` do their work in a child.
+fn under_roots(system: &System, roots: &HashSet<Pid>, pid: Pid) -> bool {
+    let mut current = Some(pid);
+    // Bounded in case a reused PID makes the parent chain loop
+    for _ in 0..64 {
+        match current {
+            Some(pid) if roots.contains(&pid) => return true,
+            Some(pid) => current = system.process(pid).and_then(|p| p.parent()),
+            None => return false,
+        }
+    }
+    false
+}
+
+fn measure_processes(pids: &[u32]) -> FleetUsage {
+    let roots: HashSet<Pid> = pids.iter().map(|pid| Pid::from_u32(*pid)).collect();
+    let refresh = ProcessRefreshKind::new().with_cpu().with_memory();
+
+    // CPU usage is a delta between two refreshes of the whole table
+    let mut system = System::new();
+    system.refresh_processes_specifics(refresh);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_processes_specifics(refresh);
+
+    let mut usage = FleetUsage {
+        cpu_percent: 0.0,
+        memory_bytes: 0,
+        process_count: 0,
+    };
+    for (pid, process) in system.processes() {
+        // Linux lists each thread as a task under its process, with the
+        // process's memory and a share of its CPU already counted there
+        if process.thread_kind().is_some() || !under_roots(&system, &roots, *pid) {
+            continue;
+        }
+        usage.cpu_percent += process.cpu_usage();
+        usage.memory_bytes += process.memory();
+        if roots.contains(pid) {
+            usage.process_count += 1;
+        }
+    }
+    usage
+}
+
+fn measure() -> ResourceUsage {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.refresh_cpu();
+    let cpu_count = system.cpus().len().max(1);
+
+    let cpu_load = if cfg!(windows) {
+        // CPU usage is a delta between two refreshes
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        system.refresh_cpu();
+        f64::from(system.global_cpu_info().cpu_usage()) / 100.0
+    } else {
+        System::load_average().one / cpu_count as f64
+    };
+
+    ResourceUsage {
+        total_memory_bytes: system.total_memory(),
+        available_memory_bytes: system.available_memory(),
+        cpu_load,
+        cpu_count,
+    }
+}
+
+pub async fn current_usage() -> Result<ResourceUsage, String> {
+    tauri::async_runtime::spawn_blocking(measure)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Describes the first threshold from settings that `usage` crosses.
+pub fn exhausted_reason(usage: &ResourceUsage, settings: &Settings) -> Option<String> {
+    let available_mb = usage.available_memory_bytes / (1024 * 1024);
+    if available_mb < settings.min_available_memory_mb {
+        return Some(format!(
+            "Only {} MB of memory available (minimum {} MB)",
+            available_mb, settings.min_available_memory_mb
+        ));
+    }
+
+    if usage.cpu_load > settings.max_cpu_load {
+        return Some(format!(
+            "CPU load is {:.2} per core (maximum {:.2})",
+            usage.cpu_load, settings.max_cpu_load
+        ));
+    }
+
+    None
+}
+
+/// Reports current memory and CPU load and whether the spawn guard's
+/// thresholds are crossed, whether or not the guard is enabled.
+#[tauri::command]
+pub async fn check_system_resources() -> Result<ResourceCheck, String> {
+    let settings = crate::settings::load_settings().await?;
+    let usage = current_usage().await?;
+
+    Ok(ResourceCheck {
+        exhausted: exhausted_reason(&usage, &settings),
+        usage,
+    })
+}
+
+/// Sums CPU and memory over every running managed process, including the
+/// processes they started, for a fleet-wide usage gauge.
+#[tauri::command]
+pub async fn total_process_resources(state: tauri::State<'_, ProcessMap>) -> Result<FleetUsage, String> {
+    let pids: Vec<u32> = state
+        .lock()
+        .await
+        .values()
+        .filter(|process| process.exit_code.is_none())
+        .filter_map(|process| process.pid)
+        .collect();
+
+    tauri::async_runtime::spawn_blocking(move || measure_processes(&pids))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn threads_are_not_counted_as_processes() {
+        // A second thread that stays alive while this process is measured
+        let (done, wait) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            let _ = wait.recv();
+        });
+        let usage = measure_processes(&[std::process::id()]);
+        drop(done);
+        worker.join().unwrap();
+
+        let mut system = System::new();
+        system.refresh_processes_specifics(ProcessRefreshKind::new().with_memory());
+        let own = system.process(Pid::from_u32(std::process::id())).unwrap().memory();
+
+        assert_eq!(usage.process_count, 1);
+        assert!(
+            usage.memory_bytes < own + own / 2,
+            "{} bytes counted for a process using {}",
+            usage.memory_bytes,
+            own
+        );
+    }
+}
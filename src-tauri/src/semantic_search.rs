@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+use crate::fs_utils;
+use crate::ollama;
+use crate::requests::{self, CancelFlag, OllamaRequestMap, RequestMap};
+
+/// Files embedded per `/api/embed` request.
+const EMBEDDING_BATCH_SIZE: usize = 16;
+
+/// Larger files are assumed not to be hand-written sources and are skipped.
+const MAX_EMBED_FILE_BYTES: u64 = 256 * 1024;
+
+/// Only the start of each file is embedded; it's usually the most telling
+/// part and keeps requests within the model's context.
+const MAX_EMBED_CHARS: usize = 8000;
+
+/// Directories that are never worth searching.
+const EXCLUDED_DIRS: &[&str] = &["node_modules", "target", "dist", "build"];
+
+pub struct CachedEmbedding {
+    modified: SystemTime,
+    vector: Vec<f32>,
+}
+
+/// File embeddings keyed by model and path. An entry is reused only while the
+/// file's mtime is unchanged.
+pub type EmbeddingCache = Arc<Mutex<HashMap<(String, PathBuf), CachedEmbedding>>>;
+
+pub fn create_embedding_cache() -> EmbeddingCache {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedFile {
+    pub path: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SearchResultsPayload {
+    request_id: String,
+    results: Vec<RankedFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SearchDonePayload {
+    request_id: String,
+    ranked: usize,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn is_excluded(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.') || EXCLUDED_DIRS.contains(&name))
+        .unwrap_or(false)
+}
+
+/// Reads the text to embed for a file, or `None` for large or binary files.
+async fn embeddable_text(path: &Path, size: u64) -> Option<String> {
+    if size == 0 || size > MAX_EMBED_FILE_BYTES {
+        return None;
+    }
+
+    let text = String::from_utf8(tokio::fs::read(path).await.ok()?).ok()?;
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    Some(text.chars().take(MAX_EMBED_CHARS).collect())
+}
+
+/// Which embedding model to rank with and how many matches to keep.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingOptions {
+    pub model: String,
+    /// Ollama host; see `ollama::resolve_host`.
+    pub host: Option<String>,
+    pub top_k: usize,
+}
+
+struct SearchParams<'a> {
+    root: &'a str,
+    query: &'a str,
+    model: &'a str,
+    base_url: &'a str,
+    top_k: usize,
+    request_id: &'a str,
+}
+
+/// Returns how many files were ranked and whether the search was cancelled.
+async fn run_search(
+    app: &AppHandle,
+    params: SearchParams<'_>,
+    cache: &EmbeddingCache,
+    flag: &CancelFlag,
+) -> Result<(usize, bool), String> {
+    let client = reqwest::Client::new();
+    let query = ollama::embed(&client, params.base_url, params.model, &[params.query.to_string()]);
+    let Some(query_vector) = flag.run(query).await else {
+        return Ok((0, true));
+    };
+    let query_vector = query_vector?.remove(0);
+
+    let root = PathBuf::from(params.root);
+    let files = tokio::task::spawn_blocking(move || fs_utils::walk_files(&root, &is_excluded))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?
+        .files;
+
+    let mut ranked: Vec<RankedFile> = Vec::new();
+    let mut ranked_count = 0;
+
+    for batch in files.chunks(EMBEDDING_BATCH_SIZE) {
+        if flag.is_cancelled() {
+            return Ok((ranked_count, true));
+        }
+
+        let mut vectors: Vec<(PathBuf, Vec<f32>)> = Vec::new();
+        let mut pending: Vec<(PathBuf, SystemTime, String)> = Vec::new();
+
+        for path in batch {
+            let Ok(metadata) = tokio::fs::metadata(path).await else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let key = (params.model.to_string(), path.clone());
+
+            match cache.lock().await.get(&key) {
+                Some(cached) if cached.modified == modified => {
+                    vectors.push((path.clone(), cached.vector.clone()));
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Some(text) = embeddable_text(path, metadata.len()).await {
+                pending.push((path.clone(), modified, text));
+            }
+        }
+
+        if !pending.is_empty() {
+            let inputs: Vec<String> = pending.iter().map(|(_, _, text)| text.clone()).collect();
+            // Cancelling drops the request rather than waiting for it
+            let embedding = ollama::embed(&client, params.base_url, params.model, &inputs);
+            let Some(embedded) = flag.run(embedding).await else {
+                return Ok((ranked_count, true));
+            };
+            let embedded = embedded?;
+
+            let mut cache = cache.lock().await;
+            for ((path, modified, _), vector) in pending.into_iter().zip(embedded) {
+                cache.insert(
+                    (params.model.to_string(), path.clone()),
+                    CachedEmbedding {
+                        modified,
+                        vector: vector.clone(),
+                    },
+                );
+                vectors.push((path, vector));
+            }
+        }
+
+        if vectors.is_empty() {
+            continue;
+        }
+
+        ranked_count += vectors.len();
+        ranked.extend(vectors.into_iter().map(|(path, vector)| RankedFile {
+            path: path.to_string_lossy().to_string(),
+            score: cosine_similarity(&query_vector, &vector),
+        }));
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked.truncate(params.top_k);
+
+        let _ = app.emit(
+            "semantic-search-results",
+            SearchResultsPayload {
+                request_id: params.request_id.to_string(),
+                results: ranked.clone(),
+            },
+        );
+    }
+
+    Ok((ranked_count, false))
+}
+
+/// Ranks text files under `root` by embedding similarity to `query`. After
+/// each batch of files a `semantic-search-results` event carries the best
+/// `top_k` matches so far; a final `semantic-search-done` event follows.
+/// Cancel with `cancel_request` or `cancel_all_ollama`.
+#[tauri::command]
+pub async fn semantic_file_search(
+    root: String,
+    query: String,
+    options: EmbeddingOptions,
+    request_id: String,
+    app: AppHandle,
+    requests_state: tauri::State<'_, RequestMap>,
+    ollama_requests: tauri::State<'_, OllamaRequestMap>,
+    cache: tauri::State<'_, EmbeddingCache>,
+) -> Result<(), String> {
+    let flag = requests::register(&requests_state, &request_id).await?;
+    let key = requests::track_ollama(&ollama_requests, &flag).await;
+    let base_url = ollama::resolve_host(options.host).await;
+
+    let params = SearchParams {
+        root: &root,
+        query: &query,
+        model: &options.model,
+        base_url: &base_url,
+        top_k: options.top_k,
+        request_id: &request_id,
+    };
+    let result = run_search(&app, params, &cache, &flag).await;
+    requests::untrack_ollama(&ollama_requests, &key).await;
+    requests::finish(&requests_state, &request_id).await;
+
+    let done = match &result {
+        Ok((ranked, cancelled)) => SearchDonePayload {
+            request_id: request_id.clone(),
+            ranked: *ranked,
+            cancelled: *cancelled,
+            error: None,
+        },
+        Err(e) => SearchDonePayload {
+            request_id: request_id.clone(),
+            ranked: 0,
+            cancelled: false,
+            error: Some(e.clone()),
+        },
+    };
+    let _ = app.emit("semantic-search-done", done);
+
+    result.map(|_| ())
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils;
+
+/// Metadata stored next to a persisted process transcript. The session id is
+/// the connection id the process had while running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub process_type: String,
+    pub command: String,
+    /// Arguments with secret-looking values redacted.
+    pub args: Vec<String>,
+    /// Transcript file: the `log_to_file` path, or one in the sessions dir.
+    pub transcript: String,
+    pub started_at_ms: u64,
+    #[serde(default)]
+    pub ended_at_ms: Option<u64>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub info: SessionInfo,
+    /// Every stdin/stdout/stderr line, tagged `[stdin]`, `[stdout]` or `[stderr]`.
+    pub transcript: String,
+}
+
+pub fn sessions_dir() -> Result<PathBuf, String> {
+    Ok(crate::settings::settings_dir()?.join("sessions"))
+}
+
+/// Default transcript location for a session without `log_to_file`.
+pub fn transcript_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(sessions_dir()?.join(format!("{}.log", session_id)))
+}
+
+fn metadata_path(session_id: &str) -> Result<PathBuf, String> {
+    // Ids come from the frontend on read, so keep them inside the directory
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid session id '{}'", session_id));
+    }
+    Ok(sessions_dir()?.join(format!("{}.json", session_id)))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+async fn write_info(info: &SessionInfo) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(info).map_err(|e| e.to_string())?;
+    fs_utils::write_atomic(&metadata_path(&info.session_id)?, json.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn read_info(path: &Path) -> Result<SessionInfo, String> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid session file {}: {}", path.display(), e))
+}
+
+/// Records a new session when a process with a transcript is spawned.
+pub async fn start(
+    session_id: &str,
+    process_type: &str,
+    command: &str,
+    args: &[String],
+    transcript: &Path,
+) -> Result<(), String> {
+    tokio::fs::create_dir_all(sessions_dir()?)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    write_info(&SessionInfo {
+        session_id: session_id.to_string(),
+        process_type: process_type.to_string(),
+        command: command.to_string(),
+        args: crate::diagnostics::redact_args(args),
+        transcript: transcript.to_string_lossy().into_owned(),
+        started_at_ms: now_ms(),
+        ended_at_ms: None,
+        exit_code: None,
+    })
+    .await
+}
+
+/// Marks a session as ended. Best effort: a missing or already-ended
+/// session is left alone.
+pub async fn finish(session_id: &str, exit_code: Option<i32>) {
+    let Ok(path) = metadata_path(session_id) else {
+        return;
+    };
+    let Ok(mut info) = read_info(&path).await else {
+        return;
+    };
+    if info.ended_at_ms.is_some() {
+        return;
+    }
+
+    info.ended_at_ms = Some(now_ms());
+    info.exit_code = exit_code;
+    let _ = write_info(&info).await;
+}
+
+/// Lists persisted sessions, newest first. Unreadable metadata files are
+/// skipped.
+#[tauri::command]
+pub async fn list_sessions() -> Result<Vec<SessionInfo>, String> {
+    let mut entries = match tokio::fs::read_dir(sessions_dir()?).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut sessions = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        if let Ok(info) = read_info(&path).await {
+            sessions.push(info);
+        }
+    }
+
+    sessions.sort_by(|a, b| b.started_at_ms.cmp(&a.started_at_ms));
+    Ok(sessions)
+}
+
+/// Loads a past session's metadata and full transcript.
+#[tauri::command]
+pub async fn read_session(session_id: String) -> Result<Session, String> {
+    let info = read_info(&metadata_path(&session_id)?)
+        .await
+        .map_err(|e| format!("Session '{}' not found: {}", session_id, e))?;
+
+    let bytes = tokio::fs::read(&info.transcript)
+        .await
+        .map_err(|e| format!("Failed to read transcript {}: {}", info.transcript, e))?;
+
+    Ok(Session {
+        transcript: String::from_utf8_lossy(&bytes).into_owned(),
+        info,
+    })
+}
+
+/// Above this many cells (lines of A times lines of B, after trimming the
+/// common start and end) the LCS table isn't built and the differing middle
+/// is reported as removed then added.
+const MAX_DIFF_CELLS: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineDiffKind {
+    Common,
+    Removed,
+    Added,
+}
+
+/// Line-level diff of `a` into `b` via longest common subsequence. Returns
+/// the operations in order and whether the diff is minimal.
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> (Vec<(LineDiffKind, &'a str)>, bool) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops: Vec<(LineDiffKind, &str)> = a[..prefix].iter().map(|l| (LineDiffKind::Common, *l)).collect();
+    let (n, m) = (middle_a.len(), middle_b.len());
+    let exact = (n + 1).saturating_mul(m + 1) <= MAX_DIFF_CELLS;
+
+    if exact {
+        // lcs[i][j]: LCS length of middle_a[i..] and middle_b[j..]
+        let width = m + 1;
+        let mut lcs = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * width + j] = if middle_a[i] == middle_b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if middle_a[i] == middle_b[j] {
+                ops.push((LineDiffKind::Common, middle_a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                ops.push((LineDiffKind::Removed, middle_a[i]));
+                i += 1;
+            } else {
+                ops.push((LineDiffKind::Added, middle_b[j]));
+                j += 1;
+            }
+        }
+        ops.extend(middle_a[i..].iter().map(|l| (LineDiffKind::Removed, *l)));
+        ops.extend(middle_b[j..].iter().map(|l| (LineDiffKind::Added, *l)));
+    } else {
+        ops.extend(middle_a.iter().map(|l| (LineDiffKind::Removed, *l)));
+        ops.extend(middle_b.iter().map(|l| (LineDiffKind::Added, *l)));
+    }
+
+    ops.extend(a[a.len() - suffix..].iter().map(|l| (LineDiffKind::Common, *l)));
+    (ops, exact)
+}
+
+/// Line-level diff of two sessions' transcripts, e.g. two agents given the
+/// same prompt with `broadcast_stdin`. Lines keep their `[stdin]`/`[stdout]`/
+/// `[stderr]` tags, so shared input shows up as common.
+#[tauri::command]
+pub async fn diff_transcripts(session_a: String, session_b: String) -> Result<serde_json::Value, String> {
+    let a = read_session(session_a).await?.transcript;
+    let b = read_session(session_b).await?.transcript;
+    let a_lines: Vec<&str> = a.lines().collect();
+    let b_lines: Vec<&str> = b.lines().collect();
+
+    let (ops, exact) = diff_lines(&a_lines, &b_lines);
+    let count = |kind| ops.iter().filter(|(k, _)| *k == kind).count();
+    let summary = serde_json::json!({
+        "common": count(LineDiffKind::Common),
+        "removed": count(LineDiffKind::Removed),
+        "added": count(LineDiffKind::Added),
+    });
+    let lines: Vec<serde_json::Value> = ops
+        .iter()
+        .map(|(kind, text)| serde_json::json!({ "kind": kind, "text": text }))
+        .collect();
+
+    Ok(serde_json::json!({ "lines": lines, "summary": summary, "exact": exact }))
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils;
+
+pub const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Base URL used for Ollama when a command isn't given an explicit host.
+    pub default_host: String,
+    /// Maximum number of concurrently managed processes.
+    pub max_processes: usize,
+    /// Autosave interval in seconds; `0` disables autosave.
+    pub autosave_interval_secs: u64,
+    /// Directory file commands are allowed to operate in, if restricted.
+    pub sandbox_root: Option<String>,
+    /// Whether spawning is restricted to an allowlist of commands.
+    pub command_allowlist_enabled: bool,
+    /// Refuse to spawn processes while memory or CPU is exhausted.
+    pub resource_check_enabled: bool,
+    /// Available memory below which spawning is refused, in MB.
+    pub min_available_memory_mb: u64,
+    /// Load per CPU above which spawning is refused.
+    pub max_cpu_load: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_host: DEFAULT_OLLAMA_HOST.to_string(),
+            max_processes: 16,
+            autosave_interval_secs: 60,
+            sandbox_root: None,
+            command_allowlist_enabled: false,
+            resource_check_enabled: false,
+            min_available_memory_mb: 512,
+            max_cpu_load: 0.95,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.default_host.starts_with("http://") || self.default_host.starts_with("https://")) {
+            return Err(format!(
+                "default_host must start with http:// or https://, got '{}'",
+                self.default_host
+            ));
+        }
+
+        if !(1..=64).contains(&self.max_processes) {
+            return Err(format!(
+                "max_processes must be between 1 and 64, got {}",
+                self.max_processes
+            ));
+        }
+
+        if self.autosave_interval_secs != 0 && !(5..=3600).contains(&self.autosave_interval_secs) {
+            return Err(format!(
+                "autosave_interval_secs must be 0 (disabled) or between 5 and 3600, got {}",
+                self.autosave_interval_secs
+            ));
+        }
+
+        if !(self.max_cpu_load > 0.0 && self.max_cpu_load <= 16.0) {
+            return Err(format!(
+                "max_cpu_load must be greater than 0 and at most 16, got {}",
+                self.max_cpu_load
+            ));
+        }
+
+        if let Some(root) = &self.sandbox_root {
+            if !Path::new(root).is_absolute() {
+                return Err(format!("sandbox_root must be an absolute path, got '{}'", root));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn settings_dir() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| home.join(".config").join("beaki"))
+        .ok_or_else(|| "Could not find home directory".to_string())
+}
+
+/// Directory for regenerable data such as detection snapshots.
+pub fn cache_dir() -> Result<PathBuf, String> {
+    dirs::cache_dir()
+        .map(|cache| cache.join("beaki"))
+        .ok_or_else(|| "Could not find cache directory".to_string())
+}
+
+pub fn settings_path() -> Result<PathBuf, String> {
+    Ok(settings_dir()?.join("settings.json"))
+}
+
+/// Loads settings from disk, falling back to defaults when the file doesn't
+/// exist yet. Missing fields are filled with their defaults.
+pub async fn load_settings() -> Result<Settings, String> {
+    let path = settings_path()?;
+
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("Failed to read settings at {:?}: {}", path, e)),
+    };
+
+    let settings: Settings = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings at {:?}: {}", path, e))?;
+
+    settings
+        .validate()
+        .map_err(|e| format!("Invalid settings at {:?}: {}", path, e))?;
+
+    Ok(settings)
+}
+
+pub async fn save_settings(settings: &Settings) -> Result<(), String> {
+    settings.validate()?;
+
+    let path = settings_path()?;
+    tokio::fs::create_dir_all(settings_dir()?)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs_utils::write_atomic(&path, json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write settings at {:?}: {}", path, e))
+}
+
+#[tauri::command]
+pub async fn read_settings() -> Result<Settings, String> {
+    load_settings().await
+}
+
+#[tauri::command]
+pub async fn write_settings(settings: Settings) -> Result<(), String> {
+    save_settings(&settings).await
+}
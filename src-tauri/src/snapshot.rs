@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tokio::sync::Mutex;
+
+use crate::fs_utils;
+
+/// Directories left out of snapshots: VCS metadata and build output change
+/// constantly without the project changing.
+const EXCLUDED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// blake3 of the contents, lowercase hex.
+    pub hash: String,
+    pub size: u64,
+    /// Modification time in milliseconds since the Unix epoch.
+    pub modified_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirSnapshot {
+    pub root: String,
+    /// Files keyed by path relative to `root`, with `/` separators.
+    pub files: BTreeMap<String, FileEntry>,
+    /// blake3 over every path and file hash in order, so two snapshots match
+    /// exactly when their root hashes do.
+    pub root_hash: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+/// Latest snapshot per canonical root. Files whose size and mtime match the
+/// cached entry aren't re-hashed.
+pub type SnapshotCache = Arc<Mutex<HashMap<PathBuf, DirSnapshot>>>;
+
+pub fn create_snapshot_cache() -> SnapshotCache {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("/"))
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn root_hash(files: &BTreeMap<String, FileEntry>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for (path, entry) in files {
+        hasher.update(path.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(entry.hash.as_bytes());
+        hasher.update(&[b'\n']);
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Walks and hashes `root`. Runs synchronously; call from `spawn_blocking`.
+fn build_snapshot(root: &Path, previous: Option<&DirSnapshot>) -> std::io::Result<DirSnapshot> {
+    let exclude = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| EXCLUDED_DIRS.contains(&name))
+            .unwrap_or(false)
+    };
+    let walk = fs_utils::walk_files(root, &exclude)?;
+
+    let mut files = BTreeMap::new();
+    for path in walk.files {
+        let Some(key) = relative_key(root, &path) else {
+            continue;
+        };
+        // Files can vanish or become unreadable mid-walk
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let size = metadata.len();
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let unchanged = previous
+            .and_then(|snapshot| snapshot.files.get(&key))
+            .filter(|entry| modified_ms != 0 && entry.size == size && entry.modified_ms == modified_ms);
+        let hash = match unchanged {
+            Some(entry) => entry.hash.clone(),
+            None => match hash_file(&path) {
+                Ok(hash) => hash,
+                Err(_) => continue,
+            },
+        };
+
+        files.insert(key, FileEntry { hash, size, modified_ms });
+    }
+
+    Ok(DirSnapshot {
+        root: root.to_string_lossy().into_owned(),
+        root_hash: root_hash(&files),
+        files,
+    })
+}
+
+/// Hashes every file under `root` (skipping `.git`, `node_modules` and
+/// `target`) into a snapshot the frontend can keep and later compare with
+/// `diff_snapshots`. Unchanged files are not re-read on repeated snapshots
+/// of the same root.
+#[tauri::command]
+pub async fn snapshot_directory(
+    root: String,
+    cache: tauri::State<'_, SnapshotCache>,
+) -> Result<DirSnapshot, String> {
+    let root =
+        std::fs::canonicalize(&root).map_err(|e| format!("Cannot snapshot '{}': {}", root, e))?;
+    let previous = cache.lock().await.get(&root).cloned();
+
+    let snapshot = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || build_snapshot(&root, previous.as_ref()))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?
+    };
+
+    cache.lock().await.insert(root, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Lists the files added, removed and changed between two snapshots.
+#[tauri::command]
+pub async fn diff_snapshots(old: DirSnapshot, new: DirSnapshot) -> Result<SnapshotDiff, String> {
+    let mut diff = SnapshotDiff::default();
+    if old.root_hash == new.root_hash {
+        return Ok(diff);
+    }
+
+    for (path, entry) in &new.files {
+        match old.files.get(path) {
+            None => diff.added.push(path.clone()),
+            Some(old_entry) if old_entry.hash != entry.hash => diff.changed.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .files
+        .keys()
+        .filter(|path| !new.files.contains_key(*path))
+        .cloned()
+        .collect();
+
+    Ok(diff)
+}
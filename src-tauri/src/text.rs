@@ -0,0 +1,351 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+use crate::fs_utils;
+
+/// How much of a file is inspected when detecting its line endings.
+const LINE_ENDING_SAMPLE_BYTES: usize = 1024 * 1024;
+/// How much of a file is fed to the encoding detector.
+const ENCODING_SAMPLE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LineEndingCounts {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LineEndingReport {
+    /// The most common style, or `None` when the sample has no line breaks.
+    pub style: Option<LineEnding>,
+    pub mixed: bool,
+    pub counts: LineEndingCounts,
+}
+
+fn count_line_endings(bytes: &[u8]) -> LineEndingCounts {
+    let mut counts = LineEndingCounts::default();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                counts.crlf += 1;
+                i += 1;
+            }
+            b'\r' => counts.cr += 1,
+            b'\n' => counts.lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    counts
+}
+
+async fn read_sample(path: &str, limit: usize) -> Result<Vec<u8>, String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut sample = Vec::new();
+    file.take(limit as u64)
+        .read_to_end(&mut sample)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(sample)
+}
+
+#[tauri::command]
+pub async fn detect_line_endings(path: String) -> Result<LineEndingReport, String> {
+    let mut sample = read_sample(&path, LINE_ENDING_SAMPLE_BYTES).await?;
+
+    // A sample cut between \r and \n would miscount one CRLF as a CR
+    if sample.len() == LINE_ENDING_SAMPLE_BYTES && sample.last() == Some(&b'\r') {
+        sample.pop();
+    }
+
+    let counts = count_line_endings(&sample);
+    let styles = [
+        (LineEnding::Lf, counts.lf),
+        (LineEnding::Crlf, counts.crlf),
+        (LineEnding::Cr, counts.cr),
+    ];
+
+    let style = styles
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map(|(style, _)| *style);
+    let mixed = styles.iter().filter(|(_, count)| *count > 0).count() > 1;
+
+    Ok(LineEndingReport {
+        style,
+        mixed,
+        counts,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct EncodingGuess {
+    /// WHATWG label, e.g. `UTF-8`, `windows-1252`, `Shift_JIS`.
+    pub encoding: String,
+    /// Rough score between 0 and 1; only meant to rank how sure the UI
+    /// should sound.
+    pub confidence: f32,
+}
+
+pub fn guess_encoding(sample: &[u8], truncated: bool) -> EncodingGuess {
+    let guess = |encoding: &'static encoding_rs::Encoding, confidence| EncodingGuess {
+        encoding: encoding.name().to_string(),
+        confidence,
+    };
+
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(sample) {
+        return guess(encoding, 1.0);
+    }
+
+    // A truncated sample may end in the middle of a multi-byte character
+    let valid_utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => truncated && e.error_len().is_none(),
+    };
+    if valid_utf8 {
+        // Pure ASCII decodes the same in every ASCII-compatible encoding
+        let confidence = if sample.is_ascii() { 0.5 } else { 1.0 };
+        return guess(encoding_rs::UTF_8, confidence);
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(sample, !truncated);
+    let (encoding, plausible) = detector.guess_assess(None, false);
+    guess(encoding, if plausible { 0.8 } else { 0.3 })
+}
+
+/// Guesses the text encoding of a file from its first 64 KiB, for
+/// pre-selecting the encoding when importing foreign text assets.
+#[tauri::command]
+pub async fn detect_encoding(path: String) -> Result<EncodingGuess, String> {
+    let sample = read_sample(&path, ENCODING_SAMPLE_BYTES).await?;
+    let truncated = sample.len() == ENCODING_SAMPLE_BYTES;
+    Ok(guess_encoding(&sample, truncated))
+}
+
+/// Read size for `validate_utf8`.
+const UTF8_VALIDATE_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct Utf8Validation {
+    pub valid: bool,
+    /// Byte offset where the first invalid sequence starts, including a
+    /// sequence cut off by the end of the file.
+    pub first_invalid_offset: Option<u64>,
+}
+
+/// Checks a whole file is UTF-8, reading it in chunks so large files aren't
+/// loaded at once. A character split across two reads is carried over to
+/// the next one.
+#[tauri::command]
+pub async fn validate_utf8(path: String) -> Result<Utf8Validation, String> {
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut buffer = vec![0u8; UTF8_VALIDATE_CHUNK_BYTES];
+    let mut pending: Vec<u8> = Vec::new();
+    // File offset of `pending[0]`
+    let mut offset = 0u64;
+
+    loop {
+        let read = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buffer[..read]);
+
+        match std::str::from_utf8(&pending) {
+            Ok(_) => {
+                offset += pending.len() as u64;
+                pending.clear();
+            }
+            Err(e) if e.error_len().is_some() => {
+                return Ok(Utf8Validation {
+                    valid: false,
+                    first_invalid_offset: Some(offset + e.valid_up_to() as u64),
+                });
+            }
+            Err(e) => {
+                // Incomplete character at the end; keep it for the next read
+                offset += e.valid_up_to() as u64;
+                pending.drain(..e.valid_up_to());
+            }
+        }
+    }
+
+    Ok(Utf8Validation {
+        valid: pending.is_empty(),
+        first_invalid_offset: (!pending.is_empty()).then_some(offset),
+    })
+}
+
+pub fn normalize_text(text: &str, style: LineEnding) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', style.as_str())
+}
+
+/// Rewrites a text file so every line break uses `style`, atomically.
+/// Returns `false` when the file already matched.
+#[tauri::command]
+pub async fn normalize_line_endings(path: String, style: LineEnding) -> Result<bool, String> {
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let normalized = normalize_text(&text, style);
+    if normalized == text {
+        return Ok(false);
+    }
+
+    fs_utils::write_atomic(Path::new(&path), normalized.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+/// Rough characters-per-token ratio for English text and code; good enough
+/// for sizing chunks without shipping a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub text: String,
+    /// Byte range of the chunk within the file, end exclusive.
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub token_estimate: usize,
+}
+
+/// Byte index `count` characters after `from`, clamped to the end.
+fn advance_chars(text: &str, from: usize, count: usize) -> usize {
+    text[from..]
+        .char_indices()
+        .nth(count)
+        .map_or(text.len(), |(i, _)| from + i)
+}
+
+/// Byte index `count` characters before `to`, clamped to the start.
+fn retreat_chars(text: &str, to: usize, count: usize) -> usize {
+    if count == 0 {
+        return to;
+    }
+    text[..to]
+        .char_indices()
+        .rev()
+        .nth(count - 1)
+        .map_or(0, |(i, _)| i)
+}
+
+/// Where to end a chunk spanning `start..limit`: after the last paragraph
+/// break, else line break, else space in its second half, or at `limit`.
+fn break_point(text: &str, start: usize, limit: usize) -> usize {
+    let window = &text[start..limit];
+    let earliest = window.len() / 2;
+    let after = |found: Option<usize>, len: usize| found.map(|i| i + len).filter(|&i| i > earliest);
+
+    after(window.rfind("\n\n"), 2)
+        .or_else(|| after(window.rfind('\n'), 1))
+        .or_else(|| after(window.rfind([' ', '\t']), 1))
+        .map_or(limit, |i| start + i)
+}
+
+/// Splits `text` into chunks of about `max_tokens` each, breaking at
+/// paragraph or line boundaries where possible. Each chunk after the first
+/// repeats roughly the last `overlap_tokens` of the previous one, starting
+/// at a line (or else word) boundary inside the overlap.
+pub fn chunk_text(text: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<Chunk> {
+    let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let overlap_chars = overlap_tokens * CHARS_PER_TOKEN;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < text.len() {
+        let limit = advance_chars(text, start, max_chars);
+        let end = if limit == text.len() {
+            limit
+        } else {
+            break_point(text, start, limit)
+        };
+
+        let chunk = &text[start..end];
+        chunks.push(Chunk {
+            index: chunks.len(),
+            text: chunk.to_string(),
+            start_byte: start,
+            end_byte: end,
+            token_estimate: estimate_tokens(chunk),
+        });
+        if end == text.len() {
+            break;
+        }
+
+        let overlap_start = retreat_chars(text, end, overlap_chars);
+        let overlap = &text[overlap_start..end];
+        let next = overlap
+            .find('\n')
+            .or_else(|| overlap.find([' ', '\t']))
+            .map(|i| overlap_start + i + 1)
+            .filter(|&i| i < end)
+            .unwrap_or(overlap_start);
+        // Always make progress, even if the overlap reaches back past `start`
+        start = if next > start { next } else { end };
+    }
+
+    chunks
+}
+
+/// Splits a UTF-8 text file into context-sized chunks for feeding to an
+/// agent or embedding model. Token counts are a characters/4 estimate.
+#[tauri::command]
+pub async fn chunk_file(
+    path: String,
+    max_tokens: usize,
+    overlap_tokens: usize,
+) -> Result<Vec<Chunk>, String> {
+    if max_tokens == 0 {
+        return Err("max_tokens must be greater than 0".to_string());
+    }
+    if overlap_tokens >= max_tokens {
+        return Err("overlap_tokens must be less than max_tokens".to_string());
+    }
+
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(chunk_text(&text, max_tokens, overlap_tokens))
+}
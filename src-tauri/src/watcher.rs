@@ -0,0 +1,355 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::fs_utils;
+
+/// How long events are collected before a batch is emitted, unless
+/// `watch_paths` is given another window.
+const DEFAULT_DEBOUNCE_MS: u64 = 100;
+
+pub struct WatcherEntry {
+    pub paths: Vec<String>,
+    pub debounce_ms: u64,
+    pub created_at_ms: u64,
+    /// Dropping the watcher stops notifications and ends its debounce task.
+    _watcher: notify::RecommendedWatcher,
+}
+
+pub type WatcherMap = Arc<Mutex<HashMap<String, WatcherEntry>>>;
+
+pub fn create_watcher_map() -> WatcherMap {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// What happened to a path over a debounce window, all events combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PathChange {
+    path: String,
+    kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct WatchEventPayload {
+    watcher_id: String,
+    root: String,
+    paths: Vec<String>,
+    changes: Vec<PathChange>,
+}
+
+/// Whether a path existed before an event and whether it exists after.
+/// Renames report the old name as removed; the new name existed before only
+/// if the rename replaced a file (`target_existed`), since notify can't say.
+fn existence(kind: &EventKind, index: usize, target_existed: bool) -> (bool, bool) {
+    match kind {
+        EventKind::Create(_) => (false, true),
+        EventKind::Remove(_) => (true, false),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => (true, false),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => (target_existed, true),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if index == 0 => (true, false),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => (target_existed, true),
+        _ => (true, true),
+    }
+}
+
+/// Net change of a path from whether it existed before its first event and
+/// after its last one. A file created and removed within the window (a save
+/// temp file, say) nets out to nothing.
+fn net_change(existed_before: bool, exists_after: bool) -> Option<ChangeKind> {
+    match (existed_before, exists_after) {
+        (false, false) => None,
+        (false, true) => Some(ChangeKind::Created),
+        (true, false) => Some(ChangeKind::Removed),
+        (true, true) => Some(ChangeKind::Modified),
+    }
+}
+
+/// Picks the watched root an event path belongs to, preferring the most
+/// specific root when watched paths are nested.
+fn root_for(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+}
+
+fn is_relevant(kind: &EventKind) -> bool {
+    !matches!(kind, EventKind::Access(_))
+}
+
+/// Files under the watched roots when watching starts, so a rename onto one
+/// of them can be told apart from a rename to a new name.
+fn index_files(roots: &[PathBuf]) -> HashSet<PathBuf> {
+    roots
+        .iter()
+        .filter_map(|root| fs_utils::walk_files(root, &|_| false).ok())
+        .flat_map(|walk| walk.files)
+        .collect()
+}
+
+/// Brings the file index in line with a path's state after a batch. A removed
+/// path that wasn't a known file may have been a directory, so anything
+/// under it goes too.
+fn update_index(known: &mut HashSet<PathBuf>, path: &Path, exists_after: bool) {
+    if exists_after {
+        known.insert(path.to_path_buf());
+    } else if !known.remove(path) {
+        known.retain(|file| !file.starts_with(path));
+    }
+}
+
+async fn debounce_events(
+    app: AppHandle,
+    watcher_id: String,
+    roots: Vec<PathBuf>,
+    window: Duration,
+    mut rx: mpsc::UnboundedReceiver<notify::Result<Event>>,
+) {
+    // Events arriving during the walk wait in the channel
+    let walk_roots = roots.clone();
+    let mut known = tokio::task::spawn_blocking(move || index_files(&walk_roots))
+        .await
+        .unwrap_or_default();
+
+    while let Some(first) = rx.recv().await {
+        let mut batch = vec![first];
+
+        let deadline = tokio::time::Instant::now() + window;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            batch.push(event);
+        }
+
+        // Per root, each path in first-seen order with its existence before
+        // the first event and after the latest one
+        let mut by_root: BTreeMap<PathBuf, Vec<(PathBuf, bool, bool)>> = BTreeMap::new();
+        for event in batch.into_iter().flatten() {
+            if !is_relevant(&event.kind) {
+                continue;
+            }
+            for (index, path) in event.paths.into_iter().enumerate() {
+                let Some(root) = root_for(&path, &roots) else {
+                    continue;
+                };
+                let (before, after) = existence(&event.kind, index, known.contains(&path));
+                let paths = by_root.entry(root).or_default();
+                match paths.iter_mut().find(|(seen, _, _)| *seen == path) {
+                    Some((_, _, exists_after)) => *exists_after = after,
+                    None => paths.push((path, before, after)),
+                }
+            }
+        }
+
+        for (root, paths) in by_root {
+            for (path, _, after) in &paths {
+                update_index(&mut known, path, *after);
+            }
+            let changes: Vec<PathChange> = paths
+                .into_iter()
+                .filter_map(|(path, before, after)| {
+                    net_change(before, after).map(|kind| PathChange {
+                        path: path.to_string_lossy().to_string(),
+                        kind,
+                    })
+                })
+                .collect();
+            if changes.is_empty() {
+                continue;
+            }
+
+            let _ = app.emit(
+                "watch-event",
+                WatchEventPayload {
+                    watcher_id: watcher_id.clone(),
+                    root: root.to_string_lossy().to_string(),
+                    paths: changes.iter().map(|c| c.path.clone()).collect(),
+                    changes,
+                },
+            );
+        }
+    }
+}
+
+/// Watches several paths recursively with a single debounced watcher and
+/// returns its id. Events are collected for `debounce_ms` (default 100) and
+/// emitted as `watch-event` events tagged with the watched root they fall
+/// under, with each path's net change over the window. A rename onto an
+/// existing file is reported as modified.
+#[tauri::command]
+pub async fn watch_paths(
+    paths: Vec<String>,
+    debounce_ms: Option<u64>,
+    app: AppHandle,
+    state: tauri::State<'_, WatcherMap>,
+) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("No paths to watch".to_string());
+    }
+
+    let roots = paths
+        .iter()
+        .map(|p| std::fs::canonicalize(p).map_err(|e| format!("Cannot watch '{}': {}", p, e)))
+        .collect::<Result<Vec<PathBuf>, String>>()?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    for root in &roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {:?}: {}", root, e))?;
+    }
+
+    let debounce_ms = debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS);
+    let watcher_id = uuid::Uuid::new_v4().to_string();
+    tauri::async_runtime::spawn(debounce_events(
+        app,
+        watcher_id.clone(),
+        roots,
+        Duration::from_millis(debounce_ms),
+        rx,
+    ));
+
+    state.lock().await.insert(
+        watcher_id.clone(),
+        WatcherEntry {
+            paths,
+            debounce_ms,
+            created_at_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            _watcher: watcher,
+        },
+    );
+
+    Ok(watcher_id)
+}
+
+#[tauri::command]
+pub async fn unwatch(watcher_id: String, state: tauri::State<'_, WatcherMap>) -> Result<(), String> {
+    state
+        .lock()
+        .await
+        .remove(&watcher_id)
+        .map(|_| ())
+        .ok_or_else(|| format!("Watcher with ID '{}' not found", watcher_id))
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatcherInfo {
+    pub watcher_id: String,
+    pub paths: Vec<String>,
+    pub debounce_ms: u64,
+    pub created_at_ms: u64,
+}
+
+/// Lists active `watch_paths` watchers, oldest first.
+#[tauri::command]
+pub async fn list_watchers(state: tauri::State<'_, WatcherMap>) -> Result<Vec<WatcherInfo>, String> {
+    let mut watchers: Vec<WatcherInfo> = state
+        .lock()
+        .await
+        .iter()
+        .map(|(id, entry)| WatcherInfo {
+            watcher_id: id.clone(),
+            paths: entry.paths.clone(),
+            debounce_ms: entry.debounce_ms,
+            created_at_ms: entry.created_at_ms,
+        })
+        .collect();
+
+    watchers.sort_by_key(|w| w.created_at_ms);
+    Ok(watchers)
+}
+
+/// Stops every active watcher and returns how many there were.
+#[tauri::command]
+pub async fn unwatch_all(state: tauri::State<'_, WatcherMap>) -> Result<usize, String> {
+    let mut watchers = state.lock().await;
+    let count = watchers.len();
+    watchers.clear();
+    Ok(count)
+}
+
+/// How often `wait_for_file` re-checks when no watcher event arrives, and the
+/// only check it has when the parent directory can't be watched.
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Resolves as soon as `path` exists, or returns `false` after `timeout_ms`.
+/// Watches the parent directory for changes, polling as a fallback when the
+/// parent can't be watched (e.g. it doesn't exist yet).
+#[tauri::command]
+pub async fn wait_for_file(path: String, timeout_ms: u64) -> Result<bool, String> {
+    let target = PathBuf::from(&path);
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watcher = target.parent().and_then(|parent| {
+        let mut watcher = notify::recommended_watcher(move |_: notify::Result<Event>| {
+            let _ = tx.send(());
+        })
+        .ok()?;
+        watcher.watch(parent, RecursiveMode::NonRecursive).ok()?;
+        Some(watcher)
+    });
+
+    loop {
+        // Checked after the watcher is set up so a file created in between
+        // isn't missed
+        if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+            return Ok(true);
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        let wait = FILE_POLL_INTERVAL.min(deadline - now);
+
+        if watcher.is_some() {
+            let _ = tokio::time::timeout(wait, rx.recv()).await;
+        } else {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_onto_known_file_is_modified() {
+        let mut known = HashSet::from([PathBuf::from("/w/a.txt"), PathBuf::from("/w/dir/b.txt")]);
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+
+        let (before, after) = existence(&rename, 1, known.contains(Path::new("/w/a.txt")));
+        assert_eq!(net_change(before, after), Some(ChangeKind::Modified));
+        let (before, after) = existence(&rename, 1, known.contains(Path::new("/w/new.txt")));
+        assert_eq!(net_change(before, after), Some(ChangeKind::Created));
+
+        // Removing a directory forgets the files under it
+        update_index(&mut known, Path::new("/w/dir"), false);
+        assert!(!known.contains(Path::new("/w/dir/b.txt")));
+        update_index(&mut known, Path::new("/w/new.txt"), true);
+        assert!(known.contains(Path::new("/w/new.txt")));
+    }
+}